@@ -0,0 +1,168 @@
+use std::error::Error;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use borsh::BorshDeserialize;
+use clap::{Parser, Subcommand};
+use counter::{
+    instruction::{close, decode_returned_count, find_counter_address, increment_signed, initialize, read},
+    state::{AccountTag, Counter, RateLimit},
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+
+/* Command line client of the counter program.
+   The RPC URL and the keypair default to the ones of the Solana CLI config file,
+   so `solana config set --url localhost` is enough to target a local solana-test-validator.
+*/
+#[derive(Parser)]
+#[command(about = "Create, increment, read and close counters")]
+struct Cli {
+    /// Counter program id, as printed by `solana program deploy`
+    #[arg(long)]
+    program_id: Pubkey,
+    /// Defaults to json_rpc_url of the config file
+    #[arg(long, short = 'u')]
+    url: Option<String>,
+    /// Fee payer and signer. Defaults to keypair_path of the config file
+    #[arg(long, short = 'k')]
+    keypair: Option<String>,
+    /// Solana CLI config file. Defaults to ~/.config/solana/cli/config.yml
+    #[arg(long, short = 'C')]
+    config: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Creates a counter owned by the keypair, at the PDA seeded by [keypair, label]
+    Create {
+        label: String,
+        #[arg(long, default_value_t = 1)]
+        step: u64,
+        #[arg(long, default_value_t = 0)]
+        min: u64,
+        #[arg(long, default_value_t = u64::MAX)]
+        max: u64,
+        #[arg(long)]
+        wrap: bool,
+        /// Increments allowed per signer and window, 0 disables the rate limit
+        #[arg(long, default_value_t = 0)]
+        max_increments: u32,
+        #[arg(long, default_value_t = 0)]
+        window_slots: u64,
+    },
+    /// Increments the counter, signed by the keypair
+    Increment {
+        counter: Pubkey,
+    },
+    /// Prints the count, including the pending increments of its shards
+    Get {
+        counter: Pubkey,
+    },
+    /// Closes the counter and its shards. Only the creator can close a counter
+    Close {
+        counter: Pubkey,
+        /// Receives the rent, defaults to the keypair
+        #[arg(long)]
+        recipient: Option<Pubkey>,
+    },
+}
+
+fn load_config(path: Option<&String>) -> solana_cli_config::Config {
+    path.cloned()
+        .or_else(|| solana_cli_config::CONFIG_FILE.clone())
+        .and_then(|path| solana_cli_config::Config::load(&path).ok())
+        .unwrap_or_default()
+}
+
+fn fetch_counter(client: &RpcClient, counter: &Pubkey) -> Result<Counter, Box<dyn Error>> {
+    let data = client.get_account_data(counter)?;
+    // The history region follows the counter, so trailing bytes are ignored
+    let counter = Counter::deserialize(&mut &data[..])?;
+    if counter.tag != AccountTag::Counter {
+        return Err("account is not a counter".into())
+    }
+    Ok(counter)
+}
+
+fn send(client: &RpcClient, payer: &Keypair, instruction: Instruction) -> Result<(), Box<dyn Error>> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        client.get_latest_blockhash()?,
+    );
+    let signature = client.send_and_confirm_transaction(&transaction)?;
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+// Simulates Read, so that the count includes the pending shard counts, and decodes its return data
+fn read_count(
+    client: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+    counter: &Pubkey,
+) -> Result<u64, Box<dyn Error>> {
+    let state = fetch_counter(client, counter)?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[read(*program_id, *counter, state.shard_count)?],
+        Some(&payer.pubkey()),
+        &[payer],
+        client.get_latest_blockhash()?,
+    );
+    let result = client.simulate_transaction(&transaction)?.value;
+    if let Some(err) = result.err {
+        return Err(format!("Read failed: {}", err).into())
+    }
+    let return_data = result.return_data.ok_or("Read returned no data")?;
+    let returning_program = return_data.program_id.parse::<Pubkey>()?;
+    let data = BASE64.decode(&return_data.data.0)?;
+    Ok(decode_returned_count(program_id, &returning_program, &data).ok_or("Read returned no count")?)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let config = load_config(cli.config.as_ref());
+    let url = cli.url.unwrap_or(config.json_rpc_url);
+    let keypair_path = cli.keypair.unwrap_or(config.keypair_path);
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| format!("failed to read keypair {}: {}", keypair_path, err))?;
+    let client = RpcClient::new_with_commitment(url, CommitmentConfig::confirmed());
+    let program_id = cli.program_id;
+
+    match cli.command {
+        Command::Create {label, step, min, max, wrap, max_increments, window_slots} => {
+            let (counter, _) = find_counter_address(&program_id, &payer.pubkey(), &label);
+            let rate_limit = RateLimit {max_increments, window_slots};
+            let instruction = initialize(program_id, payer.pubkey(), label, step, min, max, wrap, rate_limit)?;
+            send(&client, &payer, instruction)?;
+            println!("Counter: {}", counter);
+        }
+        Command::Increment {counter} => {
+            // A rate limited counter tracks the signer in a PDA it pays for
+            let state = fetch_counter(&client, &counter)?;
+            let instruction = increment_signed(program_id, counter, payer.pubkey(), state.rate_limit.is_enabled())?;
+            send(&client, &payer, instruction)?;
+            println!("Count: {}", read_count(&client, &payer, &program_id, &counter)?);
+        }
+        Command::Get {counter} => {
+            println!("Count: {}", read_count(&client, &payer, &program_id, &counter)?);
+        }
+        Command::Close {counter, recipient} => {
+            let state = fetch_counter(&client, &counter)?;
+            let recipient = recipient.unwrap_or_else(|| payer.pubkey());
+            send(&client, &payer, close(program_id, counter, payer.pubkey(), recipient, state.shard_count)?)?;
+            println!("Closed {}", counter);
+        }
+    }
+
+    Ok(())
+}
@@ -5,10 +5,20 @@ use thiserror::Error;
 pub enum CounterError {
     #[error("Instruction not implemented")]
     NotImplemented,
+    #[error("Counter would go above its maximum")]
+    Overflow,
+    #[error("Counter would go below its minimum")]
+    Underflow,
+    #[error("Signer went over the increment rate limit")]
+    RateLimited,
+    #[error("A counter with this name already exists in the set")]
+    DuplicateName,
+    #[error("No counter with this name in the set")]
+    NameNotFound,
 }
 
 impl From<CounterError> for ProgramError {
     fn from(e: CounterError) -> Self {
         ProgramError::Custom(e as u32)
     }
-}
\ No newline at end of file
+}
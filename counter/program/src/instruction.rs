@@ -1,4 +1,13 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::get_return_data,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+
+use crate::state::{RateLimit, RATE_LIMIT_SEED, SHARD_SEED};
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub enum CounterInstruction {
@@ -6,5 +15,190 @@ pub enum CounterInstruction {
         try_from_slice ==> Deserializes the instance from a slice of bytes.
     */
 
-    Increment, // unsigned byte
-}
\ No newline at end of file
+    /* Increment and IncrementBy are public and take [counter] and optionally [counter, signer].
+     Decrement, Reset and Set can only be signed by the creator and take [counter, creator].
+     Every change is recorded in the history region with the signer, if any,
+     and the new count is returned with set_return_data as a borsh u64.
+     On a rate limited counter, Increment and IncrementBy take
+     [counter, signer, signer rate limit PDA, system program] and the signer pays for its PDA.
+     IncrementBy counts as amount / step increments, rounded up.
+    */
+    Increment, // unsigned byte, moves up by step
+    Decrement, // moves down by step, creator only
+    Reset, // back to min, creator only
+    Set {value: u64}, // value must be within the bounds, creator only
+    IncrementBy {amount: u64},
+    // Creates the counter as a PDA seeded by [payer, label], starting at min. label is at most 32 bytes
+    // With wrap the counter wraps around between min and max instead of failing
+    // Accounts: [payer, counter, system program]
+    Initialize {label: String, step: u64, min: u64, max: u64, wrap: bool, rate_limit: RateLimit},
+    // Reallocates the history region to hold capacity entries, keeping the most recent ones.
+    // Only the creator can resize. It funds the extra rent and gets the freed rent back when shrinking
+    // Accounts: [counter, creator, system program]
+    ResizeHistory {capacity: u32},
+    // Creates shard_count Shard PDAs, indexes 0 to shard_count - 1. A counter can only be sharded once,
+    // by its creator who pays for the shards
+    // Accounts: [counter, creator, system program, ...shards in index order]
+    CreateShards {shard_count: u8},
+    // Adds step to a shard, e.g. picked by hashing the signer. The counter is only read.
+    // Bounds are only applied by Collect and the change is not recorded in the history
+    // Accounts: [counter, shard] and optionally a signer and the rate limit accounts as for Increment
+    IncrementShard,
+    // Moves the pending counts of the given shards into the counter. Without wrap,
+    // whatever goes above max is dropped and logged
+    // Accounts: [counter, ...shards]
+    Collect,
+    // Returns the counter plus every pending shard count with set_return_data, as a borsh u64
+    // Accounts: [counter, ...all shards in index order]
+    Read,
+    // Returns the count with set_return_data as a borsh u64, without pending shard counts
+    // Accounts: [counter]
+    Get,
+    // Sends the rent of the counter and of all its shards to recipient and closes them.
+    // Only the creator can close a counter
+    // Accounts: [counter, creator, recipient, ...all shards in index order]
+    Close,
+    // Creates an empty counter set as a PDA seeded by [COUNTER_SET_SEED, payer, label]
+    // Accounts: [payer, counter set, system program]
+    CreateCounterSet {label: String},
+    // Appends a counter starting at 0, growing the account. name is at most 32 bytes
+    // Accounts: [counter set, creator, system program]. The creator funds the extra rent
+    AddCounter {name: String},
+    // Returns the new count with set_return_data as a borsh u64
+    // Accounts: [counter set]
+    IncrementNamed {name: String},
+    // Shrinks the account and sends the freed rent to the creator
+    // Accounts: [counter set, creator]
+    RemoveCounter {name: String},
+}
+
+// Instruction builders and helpers for clients and programs that CPI into the counter
+
+pub fn find_counter_address(program_id: &Pubkey, payer: &Pubkey, label: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[payer.as_ref(), label.as_bytes()], program_id)
+}
+
+pub fn find_shard_address(program_id: &Pubkey, counter: &Pubkey, index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SHARD_SEED, counter.as_ref(), &[index]], program_id)
+}
+
+pub fn find_rate_limit_address(program_id: &Pubkey, counter: &Pubkey, signer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RATE_LIMIT_SEED, counter.as_ref(), signer.as_ref()], program_id)
+}
+
+fn shard_metas(program_id: &Pubkey, counter: &Pubkey, shard_count: u8, is_writable: bool) -> Vec<AccountMeta> {
+    (0..shard_count)
+        .map(|index| {
+            let shard = find_shard_address(program_id, counter, index).0;
+            if is_writable { AccountMeta::new(shard, false) } else { AccountMeta::new_readonly(shard, false) }
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn initialize(
+    program_id: Pubkey,
+    payer: Pubkey,
+    label: String,
+    step: u64,
+    min: u64,
+    max: u64,
+    wrap: bool,
+    rate_limit: RateLimit,
+) -> Result<Instruction, ProgramError> {
+    let counter = find_counter_address(&program_id, &payer, &label).0;
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(counter, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstruction::Initialize {label, step, min, max, wrap, rate_limit}.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn increment(program_id: Pubkey, counter: Pubkey) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![AccountMeta::new(counter, false)],
+        data: CounterInstruction::Increment.try_to_vec()?,
+        program_id,
+    })
+}
+
+// Increment recorded with the signer. A rate limited counter also needs the signer window PDA,
+// which the signer pays for on its first increment
+pub fn increment_signed(
+    program_id: Pubkey,
+    counter: Pubkey,
+    signer: Pubkey,
+    rate_limited: bool,
+) -> Result<Instruction, ProgramError> {
+    let mut accounts = vec![
+        AccountMeta::new(counter, false),
+        AccountMeta::new(signer, true),
+    ];
+    if rate_limited {
+        accounts.push(AccountMeta::new(find_rate_limit_address(&program_id, &counter, &signer).0, false));
+        accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    }
+    Ok(Instruction {
+        accounts,
+        data: CounterInstruction::Increment.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn get(program_id: Pubkey, counter: Pubkey) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![AccountMeta::new_readonly(counter, false)],
+        data: CounterInstruction::Get.try_to_vec()?,
+        program_id,
+    })
+}
+
+// Returns the count including the pending counts of the shard_count shards of the counter
+pub fn read(program_id: Pubkey, counter: Pubkey, shard_count: u8) -> Result<Instruction, ProgramError> {
+    let mut accounts = vec![AccountMeta::new_readonly(counter, false)];
+    accounts.extend(shard_metas(&program_id, &counter, shard_count, false));
+    Ok(Instruction {
+        accounts,
+        data: CounterInstruction::Read.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn close(
+    program_id: Pubkey,
+    counter: Pubkey,
+    creator: Pubkey,
+    recipient: Pubkey,
+    shard_count: u8,
+) -> Result<Instruction, ProgramError> {
+    let mut accounts = vec![
+        AccountMeta::new(counter, false),
+        AccountMeta::new_readonly(creator, true),
+        AccountMeta::new(recipient, false),
+    ];
+    accounts.extend(shard_metas(&program_id, &counter, shard_count, true));
+    Ok(Instruction {
+        accounts,
+        data: CounterInstruction::Close.try_to_vec()?,
+        program_id,
+    })
+}
+
+// Count returned by the last instruction of the counter program invoked by the caller,
+// None if the return data was set by another program
+pub fn get_returned_count(program_id: &Pubkey) -> Option<u64> {
+    let (returning_program, data) = get_return_data()?;
+    decode_returned_count(program_id, &returning_program, &data)
+}
+
+// Same as get_returned_count, for return data read off chain, e.g. from a simulated transaction
+pub fn decode_returned_count(program_id: &Pubkey, returning_program: &Pubkey, data: &[u8]) -> Option<u64> {
+    if returning_program != program_id {
+        return None
+    }
+    u64::try_from_slice(data).ok()
+}
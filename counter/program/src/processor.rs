@@ -3,19 +3,260 @@ use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
-    pubkey::Pubkey,
+    pubkey::{Pubkey, MAX_SEED_LEN},
+    system_instruction,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 
+use crate::error::CounterError;
 use crate::instruction::CounterInstruction;
-use crate::state::Counter;
+use crate::state::{
+    AccountTag, Counter, CounterSet, HistoryEntry, NamedCounter, Shard, SignerWindow,
+    COUNTER_SET_SEED, MAX_NAME_LEN, RATE_LIMIT_SEED, SHARD_SEED,
+};
+
+
+pub fn assert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
+    if !statement {
+        msg!(msg);
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+// Any account of the right size would deserialize, so check that the counter belongs to this program
+fn load_counter(program_id: &Pubkey, counter_ai: &AccountInfo) -> Result<Counter, ProgramError> {
+    assert_with_msg(
+        counter_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Counter must be owned by the counter program"
+    )?;
+    // The history region follows the counter, so trailing bytes are ignored
+    let counter = Counter::deserialize(&mut &counter_ai.data.borrow()[..])?;
+    assert_with_msg(
+        counter.tag == AccountTag::Counter,
+        ProgramError::InvalidAccountData,
+        "Account is not a counter"
+    )?;
+    Ok(counter)
+}
+
+fn load_counter_set(program_id: &Pubkey, counter_set_ai: &AccountInfo) -> Result<CounterSet, ProgramError> {
+    assert_with_msg(
+        counter_set_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Counter set must be owned by the counter program"
+    )?;
+    let counter_set = CounterSet::try_from_slice(&counter_set_ai.data.borrow())?;
+    assert_with_msg(
+        counter_set.tag == AccountTag::CounterSet,
+        ProgramError::InvalidAccountData,
+        "Account is not a counter set"
+    )?;
+    Ok(counter_set)
+}
+
+// Only the creator can add or remove entries
+fn assert_counter_set_creator(counter_set: &CounterSet, creator: &AccountInfo) -> ProgramResult {
+    assert_with_msg(
+        creator.is_signer,
+        ProgramError::MissingRequiredSignature,
+        "Creator must sign."
+    )?;
+    assert_with_msg(
+        counter_set.creator == *creator.key,
+        ProgramError::MissingRequiredSignature,
+        "Only the creator can change the counter set"
+    )
+}
+
+// Only the creator can move the count anywhere else than up
+fn assert_counter_creator(counter: &Counter, creator: &AccountInfo) -> ProgramResult {
+    assert_with_msg(
+        creator.is_signer,
+        ProgramError::MissingRequiredSignature,
+        "Creator must sign."
+    )?;
+    assert_with_msg(
+        counter.creator == *creator.key,
+        ProgramError::MissingRequiredSignature,
+        "Only the creator can change the counter"
+    )
+}
+
+// Optional signer passed after the counter, recorded in the history
+fn next_signer<'a, 'b>(
+    accounts_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    let signer = accounts_iter.next();
+    if let Some(signer) = signer {
+        assert_with_msg(
+            signer.is_signer,
+            ProgramError::MissingRequiredSignature,
+            "Signer must sign."
+        )?;
+    }
+    Ok(signer)
+}
+
+// Moves all the lamports to destination and hands the emptied account back to the system program,
+// so that it can't be used again later in the same transaction
+fn close_account(ai: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let lamports = ai.lamports();
+    **destination.lamports.borrow_mut() = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    **ai.lamports.borrow_mut() = 0;
+    ai.data.borrow_mut().fill(0);
+    ai.realloc(0, false)?;
+    ai.assign(&SYSTEM_PROGRAM_ID);
+    Ok(())
+}
+
+/* Anyone can send lamports to a PDA before it is created, which makes create_account fail.
+   In that case the missing rent is topped up and the account is allocated and assigned
+   separately, which only needs the PDA signature.
+*/
+fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    system_program: &AccountInfo<'a>,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent_exempt_lamports = Rent::get()?.minimum_balance(space);
+    if new_account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                new_account.key,
+                rent_exempt_lamports,
+                space as u64,
+                owner,
+            ),
+            &[payer.clone(), new_account.clone(), system_program.clone()],
+            &[signer_seeds],
+        )
+    }
+
+    let missing_lamports = rent_exempt_lamports.saturating_sub(new_account.lamports());
+    if missing_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, new_account.key, missing_lamports),
+            &[payer.clone(), new_account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(new_account.key, space as u64),
+        &[new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(new_account.key, owner),
+        &[new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )
+}
+
+fn load_shard(program_id: &Pubkey, counter_ai: &AccountInfo, shard_ai: &AccountInfo) -> Result<Shard, ProgramError> {
+    assert_with_msg(
+        shard_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Shard must be owned by the counter program"
+    )?;
+    let shard = Shard::try_from_slice(&shard_ai.data.borrow())?;
+    assert_with_msg(
+        shard.counter == *counter_ai.key,
+        ProgramError::InvalidArgument,
+        "Shard does not belong to the counter"
+    )?;
+    Ok(shard)
+}
+
+// Counts increments, in steps, against the signer rate limit, if the counter has one.
+// The per signer window PDA is created on the first increment
+fn check_rate_limit<'a>(
+    program_id: &Pubkey,
+    counter: &Counter,
+    counter_ai: &AccountInfo<'a>,
+    signer: Option<&AccountInfo<'a>>,
+    accounts_iter: &mut std::slice::Iter<AccountInfo<'a>>,
+    increments: u64,
+) -> ProgramResult {
+    if !counter.rate_limit.is_enabled() {
+        return Ok(())
+    }
+    let signer = signer.ok_or(ProgramError::MissingRequiredSignature)?;
+    let window_ai = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let (window_key, window_bump) = Pubkey::find_program_address(
+        &[RATE_LIMIT_SEED, counter_ai.key.as_ref(), signer.key.as_ref()],
+        program_id,
+    );
+    assert_with_msg(
+        window_key == *window_ai.key,
+        ProgramError::InvalidArgument,
+        "Invalid PDA seeds for signer rate limit"
+    )?;
+    if window_ai.data_is_empty() {
+        assert_with_msg(
+            *system_program.key == SYSTEM_PROGRAM_ID,
+            ProgramError::InvalidArgument,
+            "Invalid passed in for system programs"
+        )?;
+        create_pda_account(
+            signer,
+            window_ai,
+            SignerWindow::LEN,
+            program_id,
+            system_program,
+            &[RATE_LIMIT_SEED, counter_ai.key.as_ref(), signer.key.as_ref(), &[window_bump]],
+        )?;
+    }
+
+    let mut window = SignerWindow::try_from_slice(&window_ai.data.borrow())?;
+    if let Err(err) = window.record(&counter.rate_limit, Clock::get()?.slot, increments) {
+        msg!(
+            "Signer {} is limited to {} increments per {} slots",
+            signer.key,
+            counter.rate_limit.max_increments,
+            counter.rate_limit.window_slots
+        );
+        return Err(err.into())
+    }
+    window.serialize(&mut *window_ai.data.borrow_mut())?;
+    Ok(())
+}
+
+// Records the new count in the history, writes the counter back and returns the count to the caller
+fn save_counter(counter: &mut Counter, counter_ai: &AccountInfo, signer: Option<&AccountInfo>) -> ProgramResult {
+    let clock = Clock::get()?;
+    let entry = HistoryEntry {
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+        signer: signer.map(|signer| *signer.key).unwrap_or_default(),
+        new_count: counter.count,
+    };
+    let mut data = counter_ai.data.borrow_mut();
+    counter.push_history(&mut data, &entry)?;
+    counter.serialize(&mut *data)?;
+    set_return_data(&counter.count.try_to_vec()?);
+    Ok(())
+}
 
 
 pub struct Processor {}
 
 impl Processor {
     pub fn process_instruction(
-        _program_id: &Pubkey,
+        program_id: &Pubkey,
         accounts: &[AccountInfo],
         instruction_data: &[u8]
     ) -> ProgramResult {
@@ -32,19 +273,457 @@ impl Processor {
                 // next_account_info is exposed through Solana sdk
                 // Can be used go through an iterator of accounts
                 let counter_ai = next_account_info(accounts_iter)?; // it's an AccountInfo
+                let signer = next_signer(accounts_iter)?;
 
                 /* Pulling the data from the raw account info and pack it into an object
                  Remember counter_ai is an AccountInfo, which is a struct exposed by Solana sdk
                  try_from_slice is the method inherited by Counter from the BorshDeserialize trait
+                 load_counter also checks that the account is owned by the program
                 */
-                let mut counter = Counter::try_from_slice(&counter_ai.data.borrow())?;
+                let mut counter = load_counter(program_id, counter_ai)?;
+                check_rate_limit(program_id, &counter, counter_ai, signer, accounts_iter, 1)?;
 
                 // Borsh library actually copies  the content of the buffer into a new object
-                counter.count += 1; // In the local copy of the data buffer, the counter has increased
+                // In the local copy of the data buffer, the counter has increased
+                counter.increment_by(counter.step)?;
+
+                // Write it back to the account, along with the history entry
+                save_counter(&mut counter, counter_ai, signer)?;
+            }
+            CounterInstruction::Decrement => {
+                msg!("Instruction: Decrement");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+                let creator = next_account_info(accounts_iter)?;
+
+                let mut counter = load_counter(program_id, counter_ai)?;
+                assert_counter_creator(&counter, creator)?;
+                counter.decrement_by(counter.step)?;
+                save_counter(&mut counter, counter_ai, Some(creator))?;
+            }
+            CounterInstruction::Reset => {
+                msg!("Instruction: Reset");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+                let creator = next_account_info(accounts_iter)?;
+
+                let mut counter = load_counter(program_id, counter_ai)?;
+                assert_counter_creator(&counter, creator)?;
+                counter.count = counter.min;
+                save_counter(&mut counter, counter_ai, Some(creator))?;
+            }
+            CounterInstruction::Set {value} => {
+                msg!("Instruction: Set");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+                let creator = next_account_info(accounts_iter)?;
+
+                let mut counter = load_counter(program_id, counter_ai)?;
+                assert_counter_creator(&counter, creator)?;
+                counter.set(value)?;
+                save_counter(&mut counter, counter_ai, Some(creator))?;
+            }
+            CounterInstruction::IncrementBy {amount} => {
+                msg!("Instruction: IncrementBy");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+                let signer = next_signer(accounts_iter)?;
+
+                let mut counter = load_counter(program_id, counter_ai)?;
+                // Worth as many increments as steps, so that a single call can't bypass the limit
+                check_rate_limit(program_id, &counter, counter_ai, signer, accounts_iter, amount.div_ceil(counter.step))?;
+                counter.increment_by(amount)?;
+                save_counter(&mut counter, counter_ai, signer)?;
+            }
+            CounterInstruction::Initialize {label, step, min, max, wrap, rate_limit} => {
+                msg!("Instruction: Initialize");
+                let accounts_iter = &mut accounts.iter();
+                let payer = next_account_info(accounts_iter)?;
+                let counter_ai = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                // The payer becomes the creator, and a pre-funded PDA is created without its signature
+                assert_with_msg(
+                    payer.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Payer must sign."
+                )?;
+                assert_with_msg(
+                    label.len() <= MAX_SEED_LEN,
+                    ProgramError::InvalidArgument,
+                    "Label is too long to be used as a seed"
+                )?;
+                assert_with_msg(
+                    step > 0 && min <= max,
+                    ProgramError::InvalidArgument,
+                    "Step must be positive and min at most max"
+                )?;
+
+                let (counter_key, bump) = Pubkey::find_program_address(
+                    &[payer.key.as_ref(), label.as_bytes()],
+                    program_id,
+                );
+                assert_with_msg(
+                    counter_key == *counter_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for counter"
+                )?;
+
+                create_pda_account(
+                    payer,
+                    counter_ai,
+                    Counter::LEN,
+                    program_id,
+                    system_program,
+                    &[payer.key.as_ref(), label.as_bytes(), &[bump]],
+                )?;
+
+                let counter = Counter {
+                    tag: AccountTag::Counter,
+                    count: min,
+                    creator: *payer.key,
+                    step,
+                    min,
+                    max,
+                    wrap,
+                    rate_limit,
+                    shard_count: 0,
+                    history_capacity: 0,
+                    history_len: 0,
+                    history_next: 0,
+                };
+                counter.serialize(&mut *counter_ai.data.borrow_mut())?;
+            }
+            CounterInstruction::CreateShards {shard_count} => {
+                msg!("Instruction: CreateShards");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+                let creator = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
 
-                // Write it back to the account
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                let mut counter = load_counter(program_id, counter_ai)?;
+                assert_counter_creator(&counter, creator)?;
+                assert_with_msg(
+                    counter.shard_count == 0 && shard_count > 0,
+                    ProgramError::InvalidArgument,
+                    "Counter is already sharded or shard count is zero"
+                )?;
+
+                for index in 0..shard_count {
+                    let shard_ai = next_account_info(accounts_iter)?;
+                    let (shard_key, shard_bump) = Pubkey::find_program_address(
+                        &[SHARD_SEED, counter_ai.key.as_ref(), &[index]],
+                        program_id,
+                    );
+                    assert_with_msg(
+                        shard_key == *shard_ai.key,
+                        ProgramError::InvalidArgument,
+                        "Invalid PDA seeds for shard"
+                    )?;
+                    create_pda_account(
+                        creator,
+                        shard_ai,
+                        Shard::LEN,
+                        program_id,
+                        system_program,
+                        &[SHARD_SEED, counter_ai.key.as_ref(), &[index], &[shard_bump]],
+                    )?;
+                    let shard = Shard {
+                        counter: *counter_ai.key,
+                        index,
+                        count: 0,
+                    };
+                    shard.serialize(&mut *shard_ai.data.borrow_mut())?;
+                }
+
+                counter.shard_count = shard_count;
                 counter.serialize(&mut *counter_ai.data.borrow_mut())?;
             }
+            CounterInstruction::IncrementShard => {
+                msg!("Instruction: IncrementShard");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+                let shard_ai = next_account_info(accounts_iter)?;
+                let signer = next_signer(accounts_iter)?;
+
+                let counter = load_counter(program_id, counter_ai)?;
+                check_rate_limit(program_id, &counter, counter_ai, signer, accounts_iter, 1)?;
+                let mut shard = load_shard(program_id, counter_ai, shard_ai)?;
+
+                shard.count = shard.count.checked_add(counter.step).ok_or(CounterError::Overflow)?;
+                shard.serialize(&mut *shard_ai.data.borrow_mut())?;
+            }
+            CounterInstruction::Collect => {
+                msg!("Instruction: Collect");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+
+                let mut counter = load_counter(program_id, counter_ai)?;
+                // Each shard is saved right away, so passing one twice only collects it once
+                let mut dropped: u64 = 0;
+                for shard_ai in accounts_iter {
+                    let mut shard = load_shard(program_id, counter_ai, shard_ai)?;
+                    dropped = dropped.saturating_add(counter.collect(shard.count));
+                    shard.count = 0;
+                    shard.serialize(&mut *shard_ai.data.borrow_mut())?;
+                }
+                if dropped > 0 {
+                    msg!("Counter reached its maximum, dropped {} pending increments", dropped);
+                }
+                save_counter(&mut counter, counter_ai, None)?;
+            }
+            CounterInstruction::Read => {
+                msg!("Instruction: Read");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+
+                let counter = load_counter(program_id, counter_ai)?;
+                let mut total = counter.count;
+                // Every shard is needed for the total to be exact
+                for index in 0..counter.shard_count {
+                    let shard = load_shard(program_id, counter_ai, next_account_info(accounts_iter)?)?;
+                    assert_with_msg(
+                        shard.index == index,
+                        ProgramError::InvalidArgument,
+                        "Shards must be passed in index order"
+                    )?;
+                    total = total.checked_add(shard.count).ok_or(CounterError::Overflow)?;
+                }
+                msg!("Total {}", total);
+                set_return_data(&total.try_to_vec()?);
+            }
+            CounterInstruction::Get => {
+                msg!("Instruction: Get");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+
+                let counter = load_counter(program_id, counter_ai)?;
+                msg!("Count {}", counter.count);
+                set_return_data(&counter.count.try_to_vec()?);
+            }
+            CounterInstruction::Close => {
+                msg!("Instruction: Close");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+                let creator = next_account_info(accounts_iter)?;
+                let recipient = next_account_info(accounts_iter)?;
+
+                let counter = load_counter(program_id, counter_ai)?;
+                assert_with_msg(
+                    creator.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Creator must sign."
+                )?;
+                assert_with_msg(
+                    counter.creator == *creator.key,
+                    ProgramError::MissingRequiredSignature,
+                    "Only the creator can close the counter"
+                )?;
+                assert_with_msg(
+                    counter_ai.key != recipient.key,
+                    ProgramError::InvalidArgument,
+                    "Recipient cannot be the counter itself"
+                )?;
+
+                // Pending shard counts are dropped along with the counter. Every shard is closed,
+                // otherwise it would keep its rent and block sharding a new counter at the same address
+                for index in 0..counter.shard_count {
+                    let shard_ai = next_account_info(accounts_iter)?;
+                    let shard = load_shard(program_id, counter_ai, shard_ai)?;
+                    assert_with_msg(
+                        shard.index == index,
+                        ProgramError::InvalidArgument,
+                        "Shards must be passed in index order"
+                    )?;
+                    close_account(shard_ai, recipient)?;
+                }
+                close_account(counter_ai, recipient)?;
+            }
+            CounterInstruction::CreateCounterSet {label} => {
+                msg!("Instruction: CreateCounterSet");
+                let accounts_iter = &mut accounts.iter();
+                let payer = next_account_info(accounts_iter)?;
+                let counter_set_ai = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                // The payer becomes the creator, and a pre-funded PDA is created without its signature
+                assert_with_msg(
+                    payer.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Payer must sign."
+                )?;
+                assert_with_msg(
+                    label.len() <= MAX_SEED_LEN,
+                    ProgramError::InvalidArgument,
+                    "Label is too long to be used as a seed"
+                )?;
+
+                let (counter_set_key, bump) = Pubkey::find_program_address(
+                    &[COUNTER_SET_SEED, payer.key.as_ref(), label.as_bytes()],
+                    program_id,
+                );
+                assert_with_msg(
+                    counter_set_key == *counter_set_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for counter set"
+                )?;
+
+                let counter_set = CounterSet {
+                    tag: AccountTag::CounterSet,
+                    creator: *payer.key,
+                    entries: vec![],
+                };
+                let data = counter_set.try_to_vec()?;
+                create_pda_account(
+                    payer,
+                    counter_set_ai,
+                    data.len(),
+                    program_id,
+                    system_program,
+                    &[COUNTER_SET_SEED, payer.key.as_ref(), label.as_bytes(), &[bump]],
+                )?;
+                counter_set_ai.data.borrow_mut().copy_from_slice(&data);
+            }
+            CounterInstruction::AddCounter {name} => {
+                msg!("Instruction: AddCounter");
+                let accounts_iter = &mut accounts.iter();
+                let counter_set_ai = next_account_info(accounts_iter)?;
+                let creator = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                let mut counter_set = load_counter_set(program_id, counter_set_ai)?;
+                assert_counter_set_creator(&counter_set, creator)?;
+                assert_with_msg(
+                    name.len() <= MAX_NAME_LEN,
+                    ProgramError::InvalidArgument,
+                    "Name is too long"
+                )?;
+                assert_with_msg(
+                    counter_set.find(&name).is_none(),
+                    CounterError::DuplicateName.into(),
+                    "Duplicate counter name"
+                )?;
+
+                counter_set.entries.push(NamedCounter {name, count: 0});
+                let data = counter_set.try_to_vec()?;
+
+                // Top up the rent before growing
+                let rent = Rent::get()?.minimum_balance(data.len());
+                if rent > counter_set_ai.lamports() {
+                    invoke(
+                        &system_instruction::transfer(creator.key, counter_set_ai.key, rent - counter_set_ai.lamports()),
+                        &[creator.clone(), counter_set_ai.clone(), system_program.clone()],
+                    )?;
+                }
+                counter_set_ai.realloc(data.len(), false)?;
+                counter_set_ai.data.borrow_mut().copy_from_slice(&data);
+            }
+            CounterInstruction::IncrementNamed {name} => {
+                msg!("Instruction: IncrementNamed");
+                let accounts_iter = &mut accounts.iter();
+                let counter_set_ai = next_account_info(accounts_iter)?;
+
+                let mut counter_set = load_counter_set(program_id, counter_set_ai)?;
+                let index = counter_set.find(&name).ok_or(CounterError::NameNotFound)?;
+                let entry = &mut counter_set.entries[index];
+                entry.count = entry.count.checked_add(1).ok_or(CounterError::Overflow)?;
+                set_return_data(&entry.count.try_to_vec()?);
+
+                // Same size as before, no reallocation needed
+                counter_set.serialize(&mut *counter_set_ai.data.borrow_mut())?;
+            }
+            CounterInstruction::RemoveCounter {name} => {
+                msg!("Instruction: RemoveCounter");
+                let accounts_iter = &mut accounts.iter();
+                let counter_set_ai = next_account_info(accounts_iter)?;
+                let creator = next_account_info(accounts_iter)?;
+
+                let mut counter_set = load_counter_set(program_id, counter_set_ai)?;
+                assert_counter_set_creator(&counter_set, creator)?;
+                let index = counter_set.find(&name).ok_or(CounterError::NameNotFound)?;
+
+                counter_set.entries.remove(index);
+                let data = counter_set.try_to_vec()?;
+                counter_set_ai.realloc(data.len(), false)?;
+                counter_set_ai.data.borrow_mut().copy_from_slice(&data);
+
+                // Refund the rent that the smaller account no longer needs
+                let excess = counter_set_ai
+                    .lamports()
+                    .saturating_sub(Rent::get()?.minimum_balance(data.len()));
+                **counter_set_ai.lamports.borrow_mut() -= excess;
+                **creator.lamports.borrow_mut() = creator
+                    .lamports()
+                    .checked_add(excess)
+                    .ok_or(ProgramError::InvalidAccountData)?;
+            }
+            CounterInstruction::ResizeHistory {capacity} => {
+                msg!("Instruction: ResizeHistory");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+                let creator = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                let mut counter = load_counter(program_id, counter_ai)?;
+                assert_counter_creator(&counter, creator)?;
+                let history = counter.history(&counter_ai.data.borrow())?;
+
+                // Top up the rent before growing
+                let space = Counter::space(capacity);
+                let rent = Rent::get()?.minimum_balance(space);
+                if rent > counter_ai.lamports() {
+                    invoke(
+                        &system_instruction::transfer(creator.key, counter_ai.key, rent - counter_ai.lamports()),
+                        &[creator.clone(), counter_ai.clone(), system_program.clone()],
+                    )?;
+                }
+                counter_ai.realloc(space, false)?;
+
+                // Refund the rent that the smaller account no longer needs
+                let excess = counter_ai.lamports().saturating_sub(rent);
+                **counter_ai.lamports.borrow_mut() -= excess;
+                **creator.lamports.borrow_mut() = creator
+                    .lamports()
+                    .checked_add(excess)
+                    .ok_or(ProgramError::InvalidAccountData)?;
+
+                // Lay the most recent entries out again from the start of the new region
+                let kept = &history[history.len().saturating_sub(capacity as usize)..];
+                counter.history_capacity = capacity;
+                counter.history_len = 0;
+                counter.history_next = 0;
+                let mut data = counter_ai.data.borrow_mut();
+                for entry in kept {
+                    counter.push_history(&mut data, entry)?;
+                }
+                msg!("History holds {} of {} entries", counter.history_len, capacity);
+                counter.serialize(&mut *data)?;
+            }
         }
 
         Ok(())
@@ -1,6 +1,393 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::error::CounterError;
+
+/* Counter and CounterSet are both owned by the program and have a variable size,
+ so the tag is what tells them apart.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq)]
+pub enum AccountTag {
+    Uninitialized,
+    Counter,
+    CounterSet,
+}
 
 #[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
 pub struct Counter {
+    pub tag: AccountTag,
+    pub count: u64,
+    // Set once by Initialize
+    pub creator: Pubkey, // payer of Initialize, the only key allowed to Decrement, Reset, Set and Close
+    pub step: u64, // moved by Increment and Decrement
+    pub min: u64,
+    pub max: u64,
+    pub wrap: bool, // leaving a bound wraps around to the other one instead of failing
+    pub rate_limit: RateLimit,
+    pub shard_count: u8, // number of Shard PDAs, 0 when the counter is not sharded
+    // Ring buffer of HistoryEntry stored right after the counter, resized with ResizeHistory
+    pub history_capacity: u32,
+    pub history_len: u32, // number of entries written, at most history_capacity
+    pub history_next: u32, // index of the next entry to write, which is the oldest once full
+}
+
+// At most max_increments increments per signer in any window of window_slots slots.
+// A max_increments of 0 disables the limit
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct RateLimit {
+    pub max_increments: u32,
+    pub window_slots: u64,
+}
+
+impl RateLimit {
+    pub const LEN: usize = 4 + 8;
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_increments > 0
+    }
+}
+
+pub const RATE_LIMIT_SEED: &[u8] = b"rate_limit";
+
+// Per signer PDA seeded by [RATE_LIMIT_SEED, counter, signer]
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct SignerWindow {
+    pub window_start: u64, // slot
+    pub count: u32, // increments within the window, in steps
+}
+
+impl SignerWindow {
+    pub const LEN: usize = 8 + 4;
+
+    // Counts increments more at slot, failing if the signer would go over the limit
+    pub fn record(&mut self, rate_limit: &RateLimit, slot: u64, increments: u64) -> Result<(), CounterError> {
+        if slot >= self.window_start.saturating_add(rate_limit.window_slots) {
+            self.window_start = slot;
+            self.count = 0;
+        }
+        let count = (self.count as u64)
+            .checked_add(increments)
+            .filter(|count| *count <= rate_limit.max_increments as u64)
+            .ok_or(CounterError::RateLimited)?;
+        self.count = count as u32;
+        Ok(())
+    }
+}
+
+pub const SHARD_SEED: &[u8] = b"shard";
+
+/* Shard PDA seeded by [SHARD_SEED, counter, index].
+ IncrementShard only write locks a shard, so increments on different shards can run in parallel.
+ The shard counts are pending increments, folded into the counter by Collect.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Shard {
+    pub counter: Pubkey,
+    pub index: u8,
     pub count: u64,
-}
\ No newline at end of file
+}
+
+impl Shard {
+    pub const LEN: usize = 32 + 1 + 8;
+}
+
+// Recorded every time the count changes
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct HistoryEntry {
+    pub slot: u64,
+    pub unix_timestamp: i64,
+    pub signer: Pubkey, // default pubkey when the instruction had no signer
+    pub new_count: u64,
+}
+
+impl HistoryEntry {
+    pub const LEN: usize = 8 + 8 + 32 + 8;
+}
+
+impl Counter {
+    // Size of the counter itself, without the history region
+    pub const LEN: usize = 1 + 8 + 32 + 8 * 3 + 1 + RateLimit::LEN + 1 + 4 * 3;
+
+    // Size of a counter account holding history_capacity entries
+    pub fn space(history_capacity: u32) -> usize {
+        Self::LEN + history_capacity as usize * HistoryEntry::LEN
+    }
+
+    fn history_offset(index: u32) -> usize {
+        Self::space(index)
+    }
+
+    // Writes entry over the oldest one once the buffer is full. Does nothing without a history region
+    pub fn push_history(&mut self, data: &mut [u8], entry: &HistoryEntry) -> Result<(), ProgramError> {
+        if self.history_capacity == 0 {
+            return Ok(())
+        }
+        let offset = Self::history_offset(self.history_next);
+        entry.serialize(&mut &mut data[offset..offset + HistoryEntry::LEN])?;
+        self.history_next = (self.history_next + 1) % self.history_capacity;
+        self.history_len = (self.history_len + 1).min(self.history_capacity);
+        Ok(())
+    }
+
+    // Entries from the oldest to the most recent
+    pub fn history(&self, data: &[u8]) -> Result<Vec<HistoryEntry>, ProgramError> {
+        let first = if self.history_len < self.history_capacity { 0 } else { self.history_next };
+        (0..self.history_len)
+            .map(|i| {
+                let offset = Self::history_offset((first + i) % self.history_capacity);
+                Ok(HistoryEntry::try_from_slice(&data[offset..offset + HistoryEntry::LEN])?)
+            })
+            .collect()
+    }
+
+    pub fn increment_by(&mut self, amount: u64) -> Result<(), CounterError> {
+        if self.wrap {
+            let offset = (self.count - self.min) as u128 + amount as u128 % self.range();
+            self.count = self.min + (offset % self.range()) as u64;
+        } else {
+            self.count = self.count
+                .checked_add(amount)
+                .filter(|count| *count <= self.max)
+                .ok_or(CounterError::Overflow)?;
+        }
+        Ok(())
+    }
+
+    pub fn decrement_by(&mut self, amount: u64) -> Result<(), CounterError> {
+        if self.wrap {
+            let offset = (self.count - self.min) as u128 + self.range() - amount as u128 % self.range();
+            self.count = self.min + (offset % self.range()) as u64;
+        } else {
+            self.count = self.count
+                .checked_sub(amount)
+                .filter(|count| *count >= self.min)
+                .ok_or(CounterError::Underflow)?;
+        }
+        Ok(())
+    }
+
+    // Adds pending shard counts, stopping at max unless the counter wraps.
+    // Returns the part that did not fit, which is dropped
+    pub fn collect(&mut self, amount: u64) -> u64 {
+        if self.wrap {
+            // Never fails when wrapping
+            let _ = self.increment_by(amount);
+            return 0
+        }
+        let collected = amount.min(self.max - self.count);
+        self.count += collected;
+        amount - collected
+    }
+
+    pub fn set(&mut self, value: u64) -> Result<(), CounterError> {
+        if value > self.max {
+            return Err(CounterError::Overflow)
+        }
+        if value < self.min {
+            return Err(CounterError::Underflow)
+        }
+        self.count = value;
+        Ok(())
+    }
+
+    // Number of values between min and max included, which does not fit in a u64 for the full range
+    fn range(&self) -> u128 {
+        (self.max - self.min) as u128 + 1
+    }
+}
+
+pub const COUNTER_SET_SEED: &[u8] = b"counter_set";
+pub const MAX_NAME_LEN: usize = 32;
+
+/* Named counters in a single PDA seeded by [COUNTER_SET_SEED, payer, label].
+ The account is reallocated to the serialized size every time an entry is added or removed.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct CounterSet {
+    pub tag: AccountTag,
+    pub creator: Pubkey, // only key allowed to add and remove entries
+    pub entries: Vec<NamedCounter>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct NamedCounter {
+    pub name: String,
+    pub count: u64,
+}
+
+impl CounterSet {
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter(count: u64, min: u64, max: u64, wrap: bool) -> Counter {
+        Counter {
+            tag: AccountTag::Counter,
+            count,
+            creator: Pubkey::new_unique(),
+            step: 1,
+            min,
+            max,
+            wrap,
+            rate_limit: RateLimit {max_increments: 0, window_slots: 0},
+            shard_count: 0,
+            history_capacity: 0,
+            history_len: 0,
+            history_next: 0,
+        }
+    }
+
+    #[test]
+    fn bounds_without_wrap() {
+        let mut c = counter(8, 5, 10, false);
+        c.increment_by(2).unwrap();
+        assert_eq!(c.count, 10);
+        assert_eq!(c.increment_by(1), Err(CounterError::Overflow));
+        assert_eq!(c.count, 10);
+
+        c.decrement_by(5).unwrap();
+        assert_eq!(c.count, 5);
+        assert_eq!(c.decrement_by(1), Err(CounterError::Underflow));
+        assert_eq!(c.count, 5);
+
+        let mut c = counter(u64::MAX - 1, 0, u64::MAX, false);
+        assert_eq!(c.increment_by(2), Err(CounterError::Overflow));
+    }
+
+    #[test]
+    fn wrap_around() {
+        // 6 values from 5 to 10
+        let mut c = counter(9, 5, 10, true);
+        c.increment_by(3).unwrap();
+        assert_eq!(c.count, 6);
+        c.decrement_by(2).unwrap();
+        assert_eq!(c.count, 10);
+        c.increment_by(6 * 4 + 1).unwrap();
+        assert_eq!(c.count, 5);
+        c.decrement_by(6 * 3 + 1).unwrap();
+        assert_eq!(c.count, 10);
+    }
+
+    #[test]
+    fn wrap_around_full_range() {
+        let mut c = counter(u64::MAX, 0, u64::MAX, true);
+        c.increment_by(1).unwrap();
+        assert_eq!(c.count, 0);
+        c.decrement_by(2).unwrap();
+        assert_eq!(c.count, u64::MAX - 1);
+        c.increment_by(u64::MAX).unwrap();
+        assert_eq!(c.count, u64::MAX - 2);
+    }
+
+    #[test]
+    fn set_within_bounds() {
+        let mut c = counter(5, 5, 10, true);
+        c.set(10).unwrap();
+        assert_eq!(c.count, 10);
+        assert_eq!(c.set(11), Err(CounterError::Overflow));
+        assert_eq!(c.set(4), Err(CounterError::Underflow));
+        assert_eq!(c.count, 10);
+    }
+
+    fn entry(new_count: u64) -> HistoryEntry {
+        HistoryEntry {slot: new_count, unix_timestamp: 0, signer: Pubkey::default(), new_count}
+    }
+
+    fn counts(c: &Counter, data: &[u8]) -> Vec<u64> {
+        c.history(data).unwrap().iter().map(|entry| entry.new_count).collect()
+    }
+
+    #[test]
+    fn history_without_region_is_empty() {
+        let mut c = counter(0, 0, 10, false);
+        let mut data = vec![0; Counter::LEN];
+        c.push_history(&mut data, &entry(1)).unwrap();
+        assert_eq!(c.history_len, 0);
+        assert!(c.history(&data).unwrap().is_empty());
+    }
+
+    #[test]
+    fn history_ring_buffer() {
+        let mut c = counter(0, 0, 10, false);
+        c.history_capacity = 3;
+        let mut data = vec![0; Counter::space(3)];
+
+        c.push_history(&mut data, &entry(1)).unwrap();
+        c.push_history(&mut data, &entry(2)).unwrap();
+        assert_eq!(counts(&c, &data), vec![1, 2]);
+
+        c.push_history(&mut data, &entry(3)).unwrap();
+        assert_eq!(c.history_next, 0);
+        assert_eq!(counts(&c, &data), vec![1, 2, 3]);
+
+        // Once full the oldest entry is overwritten
+        c.push_history(&mut data, &entry(4)).unwrap();
+        c.push_history(&mut data, &entry(5)).unwrap();
+        assert_eq!(c.history_len, 3);
+        assert_eq!(c.history_next, 2);
+        assert_eq!(counts(&c, &data), vec![3, 4, 5]);
+
+        // Writing the counter back leaves the history region alone
+        c.serialize(&mut &mut data[..]).unwrap();
+        assert_eq!(Counter::deserialize(&mut &data[..]).unwrap().history_len, 3);
+        assert_eq!(counts(&c, &data), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn signer_window_limit() {
+        let rate_limit = RateLimit {max_increments: 3, window_slots: 10};
+        let mut window = SignerWindow {window_start: 0, count: 0};
+
+        window.record(&rate_limit, 100, 1).unwrap();
+        assert_eq!(window.window_start, 100);
+        window.record(&rate_limit, 105, 2).unwrap();
+        assert_eq!(window.record(&rate_limit, 109, 1), Err(CounterError::RateLimited));
+        assert_eq!(window.count, 3);
+
+        // A new window starts window_slots after the previous one
+        window.record(&rate_limit, 110, 1).unwrap();
+        assert_eq!(window.window_start, 110);
+        assert_eq!(window.count, 1);
+    }
+
+    #[test]
+    fn signer_window_counts_every_step() {
+        let rate_limit = RateLimit {max_increments: 3, window_slots: 10};
+        let mut window = SignerWindow {window_start: 0, count: 0};
+
+        // A failed record leaves the window as it was
+        assert_eq!(window.record(&rate_limit, 100, 4), Err(CounterError::RateLimited));
+        assert_eq!(window.count, 0);
+        assert_eq!(window.record(&rate_limit, 100, u64::MAX), Err(CounterError::RateLimited));
+        window.record(&rate_limit, 100, 3).unwrap();
+        assert_eq!(window.count, 3);
+    }
+
+    #[test]
+    fn signer_window_rejects_overflow() {
+        let rate_limit = RateLimit {max_increments: 3, window_slots: 10};
+        let mut window = SignerWindow {window_start: 100, count: 1};
+
+        assert_eq!(window.record(&rate_limit, 105, u64::MAX), Err(CounterError::RateLimited));
+        assert_eq!(window.record(&rate_limit, 105, u64::MAX - 1), Err(CounterError::RateLimited));
+        assert_eq!(window.count, 1);
+    }
+
+    #[test]
+    fn collect_clamps_to_max() {
+        let mut c = counter(8, 5, 10, false);
+        assert_eq!(c.collect(1), 0);
+        assert_eq!(c.collect(u64::MAX), u64::MAX - 1);
+        assert_eq!(c.count, 10);
+        assert_eq!(c.collect(3), 3);
+
+        let mut c = counter(8, 5, 10, true);
+        assert_eq!(c.collect(4), 0);
+        assert_eq!(c.count, 6);
+    }
+}
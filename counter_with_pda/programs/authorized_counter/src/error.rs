@@ -5,10 +5,12 @@ use thiserror::Error;
 pub enum CounterError {
     #[error("Instruction not implemented")]
     NotImplemented,
+    #[error("Token account does not hold enough of the gating mint")]
+    InsufficientGatingBalance,
 }
 
 impl From<CounterError> for ProgramError {
     fn from(e: CounterError) -> Self {
         ProgramError::Custom(e as u32)
     }
-}
\ No newline at end of file
+}
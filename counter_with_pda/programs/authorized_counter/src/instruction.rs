@@ -3,11 +3,25 @@ use solana_program::{
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
+    system_program,
 };
 
+use crate::state::TokenGate;
+
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub enum CounterInstruction {
+    // Accounts: [counter, authority] or, on a token gated counter, [counter, holder, holder token account]
     Increment, // unsigned byte
+    // Sets or removes the token gate. Only the authority can call it.
+    // A counter created before token gates is grown to Counter::LEN when a gate is set,
+    // the authority must then be writable as it pays the extra rent
+    // Accounts: [counter, authority, system program]
+    SetTokenGate {token_gate: Option<TokenGate>},
+    // Hands the counter over, e.g. to a governance PDA. Only the authority can call it.
+    // A new counter belongs to whoever increments it first, so create it, increment it and
+    // transfer it in a single transaction to leave nobody a chance to claim it in between
+    // Accounts: [counter, authority]
+    TransferAuthority {new_authority: Pubkey},
 }
 
 pub fn increment(
@@ -24,4 +38,55 @@ pub fn increment(
         data: instruction.try_to_vec()?, // serializex instruction into a vec
         program_id,
     })
-}
\ No newline at end of file
+}
+
+// Increment signed by a holder of the gating mint instead of the authority
+pub fn increment_as_holder(
+    program_id: Pubkey,
+    counter: Pubkey,
+    holder: Pubkey,
+    holder_token_account: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(counter, false),
+            AccountMeta::new_readonly(holder, true),
+            AccountMeta::new_readonly(holder_token_account, false),
+        ],
+        data: CounterInstruction::Increment.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn set_token_gate(
+    program_id: Pubkey,
+    counter: Pubkey,
+    authority: Pubkey,
+    token_gate: Option<TokenGate>,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(counter, false),
+            AccountMeta::new(authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: CounterInstruction::SetTokenGate {token_gate}.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn transfer_authority(
+    program_id: Pubkey,
+    counter: Pubkey,
+    authority: Pubkey,
+    new_authority: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(counter, false),
+            AccountMeta::new_readonly(authority, true),
+        ],
+        data: CounterInstruction::TransferAuthority {new_authority}.try_to_vec()?,
+        program_id,
+    })
+}
@@ -1,14 +1,21 @@
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
+    program::invoke,
     program_error::ProgramError,
     pubkey::Pubkey,
+    system_instruction,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{rent::Rent, Sysvar},
 };
 
+use token_program::state::TokenAccount;
+
+use crate::error::CounterError;
 use crate::instruction::CounterInstruction;
-use crate::state::Counter;
+use crate::state::{Counter, TokenGate};
 
 
 pub fn asssert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
@@ -51,26 +58,136 @@ impl Processor {
                 )?;
 
                 // Fetches the data from the counter_ai
-                let mut counter = Counter::try_from_slice(&counter_ai.data.borrow())?;
+                let mut counter = Counter::load(counter_ai)?;
                 
-                if counter.count == 0 {
+                if counter.count == 0 && counter.token_gate.is_none() {
                     // Set the counter authority if it's the first time counter is being used
                     counter.authority = *authority_ai.key;
                 }
-                // Checks that claiming authority is the actual authority for the requested data buffer
+                match &counter.token_gate {
+                    // Anyone holding enough of the gating mint can increment instead of the authority
+                    Some(token_gate) if counter.authority != *authority_ai.key => {
+                        let token_account_ai = next_account_info(accounts_iter)?;
+                        Self::assert_token_gate(token_gate, token_account_ai, authority_ai)?;
+                    }
+                    _ => {
+                        // Checks that claiming authority is the actual authority for the requested data buffer
+                        asssert_with_msg(
+                            //counter.authority is really &counter_ai.data.authority 
+                            // authority_ai.key is the pubkey from the claimed authority according to the parameters
+                            counter.authority == *authority_ai.key,
+                            ProgramError::MissingRequiredSignature,
+                            "Attempted to increment with an invalid authority"
+                        )?;
+                    }
+                }
+
+                counter.count += 1;
+                counter.save(counter_ai)?;
+            }
+            CounterInstruction::SetTokenGate {token_gate} => {
+                msg!("Instruction: SetTokenGate");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+                let authority_ai = next_account_info(accounts_iter)?;
+
+                asssert_with_msg(
+                    authority_ai.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Authority must sign."
+                )?;
+
+                let mut counter = Counter::load(counter_ai)?;
+
+                // Unlike Increment, this never claims an unused counter
                 asssert_with_msg(
-                    //counter.authority is really &counter_ai.data.authority 
-                    // authority_ai.key is the pubkey from the claimed authority according to the parameters
-                    counter.authority == *authority_ai.key,
+                    counter.authority == *authority_ai.key && counter.count > 0,
                     ProgramError::MissingRequiredSignature,
-                    "Attempted to increment with an invalid authority"
+                    "Attempted to set the token gate with an invalid authority"
                 )?;
+                if let Some(token_gate) = &token_gate {
+                    asssert_with_msg(
+                        token_program::check_id(&token_gate.token_program),
+                        ProgramError::IncorrectProgramId,
+                        "Token gate must use the token program"
+                    )?;
+                }
 
-                counter.count += 1;
-                counter.serialize(&mut *counter_ai.data.borrow_mut())?;
+                // A legacy counter grows to fit the gate, the authority pays the extra rent
+                if token_gate.is_some() && counter_ai.data_len() < Counter::LEN {
+                    let system_program = next_account_info(accounts_iter)?;
+                    asssert_with_msg(
+                        *system_program.key == SYSTEM_PROGRAM_ID,
+                        ProgramError::InvalidArgument,
+                        "Invalid passed in for system programs"
+                    )?;
+                    let missing_lamports = Rent::get()?
+                        .minimum_balance(Counter::LEN)
+                        .saturating_sub(counter_ai.lamports());
+                    if missing_lamports > 0 {
+                        invoke(
+                            &system_instruction::transfer(authority_ai.key, counter_ai.key, missing_lamports),
+                            &[authority_ai.clone(), counter_ai.clone(), system_program.clone()],
+                        )?;
+                    }
+                    counter_ai.realloc(Counter::LEN, true)?;
+                }
+
+                counter.token_gate = token_gate;
+                counter.save(counter_ai)?;
+            }
+            CounterInstruction::TransferAuthority {new_authority} => {
+                msg!("Instruction: TransferAuthority");
+                let accounts_iter = &mut accounts.iter();
+                let counter_ai = next_account_info(accounts_iter)?;
+                let authority_ai = next_account_info(accounts_iter)?;
+
+                asssert_with_msg(
+                    authority_ai.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Authority must sign."
+                )?;
+
+                let mut counter = Counter::load(counter_ai)?;
+
+                // Unlike Increment, this never claims an unused counter
+                asssert_with_msg(
+                    counter.authority == *authority_ai.key && counter.count > 0,
+                    ProgramError::MissingRequiredSignature,
+                    "Attempted to transfer the counter with an invalid authority"
+                )?;
+
+                msg!("Authority {} -> {}", counter.authority, new_authority);
+                counter.authority = new_authority;
+                counter.save(counter_ai)?;
             }
         }
 
         Ok(())
     }
+
+    // Checks that holder signed and owns a token account of the gating mint with at least min_balance
+    fn assert_token_gate(
+        token_gate: &TokenGate,
+        token_account_ai: &AccountInfo,
+        holder_ai: &AccountInfo,
+    ) -> ProgramResult {
+        // Only the token program can write a TokenAccount it owns, so its content can be trusted
+        asssert_with_msg(
+            *token_account_ai.owner == token_gate.token_program,
+            ProgramError::IncorrectProgramId,
+            "Token account must be owned by the gating token program"
+        )?;
+        let token_account = TokenAccount::load(token_account_ai)?;
+        asssert_with_msg(
+            token_account.owner == *holder_ai.key && token_account.mint == token_gate.mint,
+            ProgramError::InvalidAccountData,
+            "Token account must hold the gating mint and be owned by the signer"
+        )?;
+        asssert_with_msg(
+            token_account.amount >= token_gate.min_balance,
+            CounterError::InsufficientGatingBalance.into(),
+            "Not enough tokens of the gating mint"
+        )
+    }
 }
\ No newline at end of file
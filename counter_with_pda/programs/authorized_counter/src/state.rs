@@ -1,8 +1,106 @@
 use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{pubkey::Pubkey};
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
 
 #[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
 pub struct Counter {
     pub authority: Pubkey, // we add authority to the counter
     pub count: u64,
-}
\ No newline at end of file
+    // When set, holders of the gating mint can increment without the authority signature
+    pub token_gate: Option<TokenGate>,
+}
+
+impl Counter {
+    // Size of the account to allocate for a counter, assuming a token gate is set
+    pub const LEN: usize = 32 + 8 + (1 + TokenGate::LEN);
+    // Counters created before token gates only hold the authority and the count
+    pub const LEGACY_LEN: usize = 32 + 8;
+
+    // A counter without the token_gate tag is a legacy counter, its gate is None.
+    // The token gate makes the size variable, so trailing bytes are ignored
+    pub fn unpack(mut data: &[u8]) -> Result<Self, ProgramError> {
+        let authority = Pubkey::deserialize(&mut data)?;
+        let count = u64::deserialize(&mut data)?;
+        let token_gate = if data.is_empty() {
+            None
+        } else {
+            Option::<TokenGate>::deserialize(&mut data)?
+        };
+        Ok(Self {authority, count, token_gate})
+    }
+
+    // A legacy counter keeps its layout as long as it has no token gate
+    pub fn pack(&self, mut data: &mut [u8]) -> ProgramResult {
+        if data.len() < Self::LEN && self.token_gate.is_none() {
+            (self.authority, self.count).serialize(&mut data)?;
+            return Ok(())
+        }
+        Ok(self.serialize(&mut data)?)
+    }
+
+    pub fn load(ai: &AccountInfo) -> Result<Self, ProgramError> {
+        Self::unpack(&ai.data.borrow())
+    }
+
+    pub fn save(&self, ai: &AccountInfo) -> ProgramResult {
+        self.pack(&mut ai.data.borrow_mut())
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct TokenGate {
+    pub token_program: Pubkey, // program owning the gating mint and the holders token accounts
+    pub mint: Pubkey,
+    pub min_balance: u64, // raw TokenAccount::amount a holder needs
+}
+
+impl TokenGate {
+    pub const LEN: usize = 32 + 32 + 8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_gate() -> TokenGate {
+        TokenGate {token_program: Pubkey::new_unique(), mint: Pubkey::new_unique(), min_balance: 10}
+    }
+
+    #[test]
+    fn legacy_counter_has_no_gate() {
+        let authority = Pubkey::new_unique();
+        let data = (authority, 7u64).try_to_vec().unwrap();
+        assert_eq!(data.len(), Counter::LEGACY_LEN);
+
+        let counter = Counter::unpack(&data).unwrap();
+        assert_eq!(counter.authority, authority);
+        assert_eq!(counter.count, 7);
+        assert!(counter.token_gate.is_none());
+    }
+
+    #[test]
+    fn legacy_counter_keeps_its_layout() {
+        let mut data = (Pubkey::new_unique(), 7u64).try_to_vec().unwrap();
+        let mut counter = Counter::unpack(&data).unwrap();
+        counter.count += 1;
+        counter.pack(&mut data).unwrap();
+        assert_eq!(Counter::unpack(&data).unwrap().count, 8);
+
+        // A gate does not fit without growing the account first
+        counter.token_gate = Some(token_gate());
+        assert!(counter.pack(&mut data).is_err());
+    }
+
+    #[test]
+    fn gated_counter_roundtrip() {
+        let mut data = vec![0; Counter::LEN];
+        let counter = Counter {authority: Pubkey::new_unique(), count: 3, token_gate: Some(token_gate())};
+        counter.pack(&mut data).unwrap();
+        let unpacked = Counter::unpack(&data).unwrap();
+        assert_eq!(unpacked.count, 3);
+        assert_eq!(unpacked.token_gate.as_ref().unwrap().min_balance, 10);
+
+        // Removing the gate leaves a None tag, followed by the stale gate bytes
+        Counter {token_gate: None, ..unpacked}.pack(&mut data).unwrap();
+        assert!(Counter::unpack(&data).unwrap().token_gate.is_none());
+    }
+}
@@ -3,5 +3,15 @@ use borsh::{BorshDeserialize, BorshSerialize};
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub enum TrackerInstruction {
     Initialize,
+    // Accounts: [tracker, user, counter program, counter, authority] and, to receive the reward when the counter
+    //  has a reward config, [reward config, user token account, reward mint, reward mint authority, token program,
+    //  token program config]
     Increment, // idea here is to be able to increment the counter (authorized_counter program) via pda and cpi
+    // Hands the reward mint over to the reward mint authority PDA, signed by its current mint authority,
+    // which becomes the admin of the reward config. The owner of the counter is the rewarded counter program
+    // Accounts: [payer, mint authority, reward config, counter, reward mint authority, reward mint, token program,
+    //  token program config, system program]
+    InitializeReward {reward_amount: u64},
+    // Accounts: [admin, reward config]
+    SetRewardAmount {reward_amount: u64},
 }
\ No newline at end of file
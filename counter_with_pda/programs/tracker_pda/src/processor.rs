@@ -11,9 +11,11 @@ use solana_program::{
     sysvar::{rent::Rent, Sysvar},
 };
 use authorized_counter::instruction::CounterInstruction;
+use token_program::state::{Mint, TokenAccount};
+use token_program::utils::assert_token_program;
 
 use crate::instruction::TrackerInstruction;
-use crate::state::Tracker;
+use crate::state::{RewardConfig, Tracker, REWARD_CONFIG_SEED, REWARD_MINT_AUTHORITY_SEED};
 
 pub fn assert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
     if !statement {
@@ -56,8 +58,9 @@ impl Processor {
                         &program_id, // program_id
                 );
 
+                // Same seeds as the ones used to create the tracker and validate it in Increment
                 let (tracker_key, tracker_bump) = Pubkey::find_program_address(
-                    &[user.key.as_ref()], // seeds
+                    &[user.key.as_ref(), counter.key.as_ref()], // seeds
                     &program_id, // program_id
                 );
 
@@ -169,7 +172,188 @@ impl Processor {
                 // controlled by the downstream program
                 msg!("User Count {}", tracker.count);
                 tracker.serialize(&mut *tracker_ai.data.borrow_mut())?;
+
+                // Reward accounts are only passed when the counter has a reward config
+                if let Some(reward_config_ai) = accounts_iter.next() {
+                    let user_token = next_account_info(accounts_iter)?;
+                    let reward_mint = next_account_info(accounts_iter)?;
+                    let mint_authority = next_account_info(accounts_iter)?;
+                    let token_program = next_account_info(accounts_iter)?;
+                    let token_config = next_account_info(accounts_iter)?;
+
+                    assert_with_msg(
+                        reward_config_ai.owner == program_id,
+                        ProgramError::IncorrectProgramId,
+                        "Reward config must be owned by the tracker program"
+                    )?;
+                    assert_token_program(token_program)?;
+                    let reward_config = RewardConfig::try_from_slice(&reward_config_ai.data.borrow())?;
+                    assert_with_msg(
+                        reward_config.counter == *counter.key
+                            && reward_config.reward_mint == *reward_mint.key
+                            && reward_config.token_program == *token_program.key,
+                        ProgramError::InvalidArgument,
+                        "Accounts do not match the reward config"
+                    )?;
+                    // Otherwise any program returning success would earn the reward without incrementing the counter
+                    assert_with_msg(
+                        reward_config.counter_program == *counter_program.key,
+                        ProgramError::IncorrectProgramId,
+                        "Counter program does not match the reward config"
+                    )?;
+                    let mint_authority_seeds = &[
+                        REWARD_MINT_AUTHORITY_SEED,
+                        counter.key.as_ref(),
+                        &[reward_config.mint_authority_bump],
+                    ];
+                    assert_with_msg(
+                        Pubkey::create_program_address(mint_authority_seeds, program_id)? == *mint_authority.key,
+                        ProgramError::InvalidArgument,
+                        "Invalid PDA seeds for reward mint authority"
+                    )?;
+                    assert_with_msg(
+                        TokenAccount::load(user_token)?.owner == *user.key,
+                        ProgramError::InvalidArgument,
+                        "Reward token account must be owned by the user"
+                    )?;
+
+                    invoke_signed(
+                        &token_program::instruction::mint_to(
+                            *token_program.key,
+                            *user_token.key,
+                            *reward_mint.key,
+                            *mint_authority.key,
+                            reward_config.reward_amount,
+                        )?,
+                        &[
+                            token_program.clone(),
+                            user_token.clone(),
+                            reward_mint.clone(),
+                            mint_authority.clone(),
+                            token_config.clone(),
+                        ],
+                        &[mint_authority_seeds],
+                    )?;
+                    msg!("Rewarded {}", reward_config.reward_amount);
+                }
             } 
+            TrackerInstruction::InitializeReward {reward_amount} => {
+                msg!("Instruction: InitializeReward");
+                let payer = next_account_info(accounts_iter)?;
+                let mint_authority = next_account_info(accounts_iter)?;
+                let reward_config_ai = next_account_info(accounts_iter)?;
+                let counter = next_account_info(accounts_iter)?;
+                let reward_mint_authority = next_account_info(accounts_iter)?;
+                let reward_mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                assert_token_program(token_program)?;
+                assert_with_msg(
+                    mint_authority.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Mint authority must sign."
+                )?;
+
+                let (reward_mint_authority_key, mint_authority_bump) = Pubkey::find_program_address(
+                    &[REWARD_MINT_AUTHORITY_SEED, counter.key.as_ref()],
+                    program_id,
+                );
+                assert_with_msg(
+                    reward_mint_authority_key == *reward_mint_authority.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for reward mint authority"
+                )?;
+                let (reward_config_key, reward_config_bump) = Pubkey::find_program_address(
+                    &[REWARD_CONFIG_SEED, counter.key.as_ref()],
+                    program_id,
+                );
+                assert_with_msg(
+                    reward_config_key == *reward_config_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for reward config"
+                )?;
+
+                // Only whoever controls the mint can turn it into a reward, so nobody else can pick the amount
+                assert_with_msg(
+                    reward_mint.owner == token_program.key,
+                    ProgramError::IncorrectProgramId,
+                    "Reward mint must belong to the token program"
+                )?;
+                assert_with_msg(
+                    Mint::load(reward_mint)?.authority == *mint_authority.key,
+                    ProgramError::InvalidArgument,
+                    "Signer must be the reward mint authority"
+                )?;
+                invoke(
+                    &token_program::instruction::set_mint_authority(
+                        *token_program.key,
+                        *reward_mint.key,
+                        *mint_authority.key,
+                        reward_mint_authority_key,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        reward_mint.clone(),
+                        mint_authority.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+
+                invoke_signed(
+                    &system_instruction::create_account(
+                        payer.key,
+                        reward_config_ai.key,
+                        Rent::get()?.minimum_balance(RewardConfig::LEN),
+                        RewardConfig::LEN as u64,
+                        program_id,
+                    ),
+                    &[payer.clone(), reward_config_ai.clone(), system_program.clone()],
+                    &[&[REWARD_CONFIG_SEED, counter.key.as_ref(), &[reward_config_bump]]],
+                )?;
+
+                let reward_config = RewardConfig {
+                    admin: *mint_authority.key,
+                    counter: *counter.key,
+                    counter_program: *counter.owner,
+                    token_program: *token_program.key,
+                    reward_mint: *reward_mint.key,
+                    reward_amount,
+                    mint_authority_bump,
+                };
+                reward_config.serialize(&mut *reward_config_ai.data.borrow_mut())?;
+            }
+            TrackerInstruction::SetRewardAmount {reward_amount} => {
+                msg!("Instruction: SetRewardAmount");
+                let admin = next_account_info(accounts_iter)?;
+                let reward_config_ai = next_account_info(accounts_iter)?;
+
+                assert_with_msg(
+                    admin.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Admin must sign."
+                )?;
+                assert_with_msg(
+                    reward_config_ai.owner == program_id,
+                    ProgramError::IncorrectProgramId,
+                    "Reward config must be owned by the tracker program"
+                )?;
+                let mut reward_config = RewardConfig::try_from_slice(&reward_config_ai.data.borrow())?;
+                assert_with_msg(
+                    reward_config.admin == *admin.key,
+                    ProgramError::MissingRequiredSignature,
+                    "Admin mismatch"
+                )?;
+
+                reward_config.reward_amount = reward_amount;
+                reward_config.serialize(&mut *reward_config_ai.data.borrow_mut())?;
+            }
         }
         Ok(())
     }
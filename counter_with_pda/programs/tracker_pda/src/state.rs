@@ -7,4 +7,27 @@ pub struct Tracker {
     pub auth_bump: u8, // bump seed of auth
     pub counter: Pubkey, // to help aggregation if needed
     pub count: u64,
-}
\ No newline at end of file
+}
+
+pub const REWARD_CONFIG_SEED: &[u8] = b"reward_config";
+pub const REWARD_MINT_AUTHORITY_SEED: &[u8] = b"reward_mint_authority";
+
+/* Optional reward configuration of a counter, a PDA seeded by [REWARD_CONFIG_SEED, counter].
+ The reward mint authority is a PDA seeded by [REWARD_MINT_AUTHORITY_SEED, counter] that only signs the reward mints.
+ The [counter] authority PDA can't be used, as it signs for whatever counter program is passed to Increment.
+ The admin is the mint authority that handed the mint over, the only one who can change the reward.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct RewardConfig {
+    pub admin: Pubkey,
+    pub counter: Pubkey,
+    pub counter_program: Pubkey, // owner of the counter, the only program Increment rewards
+    pub token_program: Pubkey,
+    pub reward_mint: Pubkey,
+    pub reward_amount: u64, // minted to the user on every increment
+    pub mint_authority_bump: u8,
+}
+
+impl RewardConfig {
+    pub const LEN: usize = 32 * 5 + 8 + 1;
+}
@@ -0,0 +1,26 @@
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum AmmError {
+    #[error("Instruction not implemented.")]
+    NotImplemented,
+    #[error("Account does not match the pool.")]
+    PoolMismatch,
+    #[error("Fee must be lower than 10000 basis points.")]
+    InvalidFee,
+    #[error("Output is below the minimum requested.")]
+    SlippageExceeded,
+    #[error("Pool has no liquidity.")]
+    EmptyPool,
+    #[error("Numerical overflow.")]
+    MathOverflow,
+    #[error("First deposit must mint more than the minimum liquidity.")]
+    InsufficientInitialLiquidity,
+}
+
+impl From<AmmError> for ProgramError {
+    fn from(e: AmmError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
@@ -0,0 +1,21 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum AmmInstruction {
+    // Creates the pool, its two vaults, the LP mint and the LP lock
+    // Accounts: [payer, pool, vault A, vault B, LP mint, LP lock, mint A, mint B,
+    //  token program, token program config, system program]
+    InitializePool {fee_bps: u16},
+    // Deposits at most max_amount_a and max_amount_b at the current pool ratio.
+    // The first deposit locks MINIMUM_LIQUIDITY of the LP tokens it mints in the LP lock
+    // Accounts: [user, pool, vault A, vault B, LP mint, user token account A, user token account B,
+    //  user LP token account, mint A, mint B, token program, token program config, LP lock]
+    AddLiquidity {max_amount_a: u64, max_amount_b: u64, min_lp_amount: u64},
+    // Burns lp_amount and pays out the matching share of both vaults
+    // Accounts: same as AddLiquidity, without the LP lock
+    RemoveLiquidity {lp_amount: u64, min_amount_a: u64, min_amount_b: u64},
+    // The direction is given by the vaults, vault in must hold the mint of the user source account
+    // Accounts: [user, pool, vault in, vault out, user source token account,
+    //  user destination token account, mint in, mint out, token program, token program config]
+    Swap {amount_in: u64, min_amount_out: u64},
+}
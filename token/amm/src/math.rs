@@ -0,0 +1,111 @@
+use solana_program::program_error::ProgramError;
+
+use crate::error::AmmError;
+use crate::state::BASIS_POINTS;
+
+// Integer square root, rounded down
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+pub fn to_u64(n: u128) -> Result<u64, ProgramError> {
+    u64::try_from(n).map_err(|_| AmmError::MathOverflow.into())
+}
+
+// a * b / c rounded down
+pub fn mul_div(a: u64, b: u64, c: u64) -> Result<u64, ProgramError> {
+    let n = (a as u128)
+        .checked_mul(b as u128)
+        .and_then(|n| n.checked_div(c as u128))
+        .ok_or(AmmError::MathOverflow)?;
+    to_u64(n)
+}
+
+// a * b / c rounded up, so that deposits always favor the pool
+pub fn mul_div_ceil(a: u64, b: u64, c: u64) -> Result<u64, ProgramError> {
+    let n = (a as u128)
+        .checked_mul(b as u128)
+        .and_then(|n| n.checked_add((c as u128).checked_sub(1)?))
+        .and_then(|n| n.checked_div(c as u128))
+        .ok_or(AmmError::MathOverflow)?;
+    to_u64(n)
+}
+
+// Constant product: the output keeps reserve_in * reserve_out from decreasing once the fee is taken
+pub fn swap_amount_out(amount_in: u64, reserve_in: u64, reserve_out: u64, fee_bps: u16) -> Result<u64, ProgramError> {
+    let amount_in_after_fee = mul_div(amount_in, BASIS_POINTS - fee_bps as u64, BASIS_POINTS)?;
+    let denominator = reserve_in
+        .checked_add(amount_in_after_fee)
+        .ok_or(AmmError::MathOverflow)?;
+    mul_div(reserve_out, amount_in_after_fee, denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn isqrt_rounds_down() {
+        for n in 0..10_000u128 {
+            let root = isqrt(n);
+            assert!(root * root <= n && (root + 1) * (root + 1) > n, "isqrt({}) = {}", n, root);
+        }
+        assert_eq!(isqrt(u64::MAX as u128 * u64::MAX as u128), u64::MAX as u128);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+    }
+
+    #[test]
+    fn mul_div_rounding() {
+        assert_eq!(mul_div(10, 10, 3).unwrap(), 33);
+        assert_eq!(mul_div_ceil(10, 10, 3).unwrap(), 34);
+        assert_eq!(mul_div(10, 9, 3).unwrap(), 30);
+        assert_eq!(mul_div_ceil(10, 9, 3).unwrap(), 30);
+        assert_eq!(mul_div_ceil(0, 9, 3).unwrap(), 0);
+        // The intermediate product does not overflow
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+        assert_eq!(mul_div_ceil(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn mul_div_errors() {
+        let overflow: ProgramError = AmmError::MathOverflow.into();
+        assert_eq!(mul_div(1, 1, 0).unwrap_err(), overflow);
+        assert_eq!(mul_div_ceil(1, 1, 0).unwrap_err(), overflow);
+        assert_eq!(mul_div(u64::MAX, 2, 1).unwrap_err(), overflow);
+        assert_eq!(mul_div_ceil(u64::MAX, 2, 1).unwrap_err(), overflow);
+    }
+
+    #[test]
+    fn swap_keeps_the_product() {
+        let (reserve_in, reserve_out) = (1_000_000u64, 2_000_000u64);
+        for fee_bps in [0, 30, 100] {
+            for amount_in in [1, 1_000, 500_000, 10_000_000] {
+                let amount_out = swap_amount_out(amount_in, reserve_in, reserve_out, fee_bps).unwrap();
+                assert!(amount_out < reserve_out);
+                let before = reserve_in as u128 * reserve_out as u128;
+                let after = (reserve_in + amount_in) as u128 * (reserve_out - amount_out) as u128;
+                assert!(after >= before, "product decreased with fee {} and input {}", fee_bps, amount_in);
+            }
+        }
+    }
+
+    #[test]
+    fn swap_amounts() {
+        // Without fee: 1_000 * 1_000 / (1_000 + 1_000)
+        assert_eq!(swap_amount_out(1_000, 1_000, 1_000, 0).unwrap(), 500);
+        // 0.3% fee: 997 * 1_000 / 1_997
+        assert_eq!(swap_amount_out(1_000, 1_000, 1_000, 30).unwrap(), 499);
+        assert_eq!(swap_amount_out(0, 1_000, 1_000, 30).unwrap(), 0);
+        // A tiny input rounds down to nothing
+        assert_eq!(swap_amount_out(1, 1_000_000, 1_000, 30).unwrap(), 0);
+    }
+}
@@ -0,0 +1,504 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{rent::Rent, Sysvar},
+};
+use token_program::state::{Mint, TokenAccount};
+use token_program::utils::assert_token_program;
+
+use crate::error::AmmError;
+use crate::instruction::AmmInstruction;
+use crate::math::{isqrt, mul_div, mul_div_ceil, swap_amount_out, to_u64};
+use crate::state::{Pool, BASIS_POINTS, LP_LOCK_SEED, LP_MINT_SEED, MINIMUM_LIQUIDITY, POOL_SEED, VAULT_SEED};
+
+pub fn assert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
+    if !statement {
+        msg!(msg);
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+/* Token CPI helpers. signer_seeds is empty when the authority is a signer of the
+   outer transaction, and holds the pool seeds when the pool PDA signs.
+*/
+#[allow(clippy::too_many_arguments)]
+fn token_transfer<'a>(
+    token_program: &AccountInfo<'a>,
+    src: &AccountInfo<'a>,
+    dst: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    token_config: &AccountInfo<'a>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &token_program::instruction::transfer(
+            *token_program.key,
+            *src.key,
+            *dst.key,
+            *authority.key,
+            *mint.key,
+            amount,
+        )?,
+        &[
+            token_program.clone(),
+            src.clone(),
+            dst.clone(),
+            authority.clone(),
+            mint.clone(),
+            token_config.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+fn token_mint_to<'a>(
+    token_program: &AccountInfo<'a>,
+    token_account: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    mint_authority: &AccountInfo<'a>,
+    token_config: &AccountInfo<'a>,
+    amount: u64,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    invoke_signed(
+        &token_program::instruction::mint_to(
+            *token_program.key,
+            *token_account.key,
+            *mint.key,
+            *mint_authority.key,
+            amount,
+        )?,
+        &[
+            token_program.clone(),
+            token_account.clone(),
+            mint.clone(),
+            mint_authority.clone(),
+            token_config.clone(),
+        ],
+        &[signer_seeds],
+    )
+}
+
+fn token_create_account<'a>(
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    space: usize,
+    token_program: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            new_account.key,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            token_program.key, // allocated here but owned by the token program
+        ),
+        &[payer.clone(), new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )
+}
+
+fn load_pool(program_id: &Pubkey, pool_ai: &AccountInfo) -> Result<Pool, ProgramError> {
+    assert_with_msg(
+        pool_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Pool must be owned by the AMM program"
+    )?;
+    Ok(Pool::try_from_slice(&pool_ai.data.borrow())?)
+}
+
+fn token_balance(ai: &AccountInfo) -> Result<u64, ProgramError> {
+    Ok(TokenAccount::load(ai)?.amount)
+}
+
+pub struct Processor {}
+
+impl Processor {
+    pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8]
+    ) -> ProgramResult {
+        let instruction = AmmInstruction::try_from_slice(instruction_data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        let accounts_iter = &mut accounts.iter();
+
+        match instruction {
+            AmmInstruction::InitializePool {fee_bps} => {
+                msg!("Instruction: InitializePool");
+                let payer = next_account_info(accounts_iter)?;
+                let pool_ai = next_account_info(accounts_iter)?;
+                let vault_a = next_account_info(accounts_iter)?;
+                let vault_b = next_account_info(accounts_iter)?;
+                let lp_mint = next_account_info(accounts_iter)?;
+                let lp_lock = next_account_info(accounts_iter)?;
+                let mint_a = next_account_info(accounts_iter)?;
+                let mint_b = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    payer.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Payer must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                assert_with_msg(
+                    (fee_bps as u64) < BASIS_POINTS,
+                    AmmError::InvalidFee.into(),
+                    "Fee must be lower than 10000 basis points"
+                )?;
+                assert_with_msg(
+                    mint_a.key < mint_b.key,
+                    ProgramError::InvalidArgument,
+                    "Mints must be sorted, mint A < mint B"
+                )?;
+                assert_with_msg(
+                    mint_a.owner == token_program.key && mint_b.owner == token_program.key,
+                    ProgramError::IncorrectProgramId,
+                    "Mints must belong to the token program"
+                )?;
+                Mint::load(mint_a)?;
+                Mint::load(mint_b)?;
+
+                let (pool_key, bump) = Pubkey::find_program_address(
+                    &[POOL_SEED, mint_a.key.as_ref(), mint_b.key.as_ref()],
+                    program_id,
+                );
+                let (vault_a_key, vault_a_bump) = Pubkey::find_program_address(
+                    &[VAULT_SEED, pool_key.as_ref(), mint_a.key.as_ref()],
+                    program_id,
+                );
+                let (vault_b_key, vault_b_bump) = Pubkey::find_program_address(
+                    &[VAULT_SEED, pool_key.as_ref(), mint_b.key.as_ref()],
+                    program_id,
+                );
+                let (lp_mint_key, lp_mint_bump) = Pubkey::find_program_address(
+                    &[LP_MINT_SEED, pool_key.as_ref()],
+                    program_id,
+                );
+                let (lp_lock_key, lp_lock_bump) = Pubkey::find_program_address(
+                    &[LP_LOCK_SEED, pool_key.as_ref()],
+                    program_id,
+                );
+                assert_with_msg(
+                    pool_key == *pool_ai.key
+                        && vault_a_key == *vault_a.key
+                        && vault_b_key == *vault_b.key
+                        && lp_mint_key == *lp_mint.key
+                        && lp_lock_key == *lp_lock.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for pool accounts"
+                )?;
+
+                let pool_seeds: &[&[u8]] = &[POOL_SEED, mint_a.key.as_ref(), mint_b.key.as_ref(), &[bump]];
+                invoke_signed(
+                    &system_instruction::create_account(
+                        payer.key,
+                        pool_ai.key,
+                        Rent::get()?.minimum_balance(Pool::LEN),
+                        Pool::LEN as u64,
+                        program_id,
+                    ),
+                    &[payer.clone(), pool_ai.clone(), system_program.clone()],
+                    &[pool_seeds],
+                )?;
+                token_create_account(
+                    payer,
+                    vault_a,
+                    TokenAccount::LEN,
+                    token_program,
+                    system_program,
+                    &[VAULT_SEED, pool_key.as_ref(), mint_a.key.as_ref(), &[vault_a_bump]],
+                )?;
+                token_create_account(
+                    payer,
+                    vault_b,
+                    TokenAccount::LEN,
+                    token_program,
+                    system_program,
+                    &[VAULT_SEED, pool_key.as_ref(), mint_b.key.as_ref(), &[vault_b_bump]],
+                )?;
+                token_create_account(
+                    payer,
+                    lp_mint,
+                    Mint::LEN,
+                    token_program,
+                    system_program,
+                    &[LP_MINT_SEED, pool_key.as_ref(), &[lp_mint_bump]],
+                )?;
+                token_create_account(
+                    payer,
+                    lp_lock,
+                    TokenAccount::LEN,
+                    token_program,
+                    system_program,
+                    &[LP_LOCK_SEED, pool_key.as_ref(), &[lp_lock_bump]],
+                )?;
+
+                // The pool PDA is the mint authority of the LP mint, so it has to sign its initialization
+                invoke_signed(
+                    &token_program::instruction::initialize_mint(
+                        *token_program.key,
+                        *lp_mint.key,
+                        *pool_ai.key,
+                    )?,
+                    &[token_program.clone(), lp_mint.clone(), pool_ai.clone(), token_config.clone()],
+                    &[pool_seeds],
+                )?;
+                for (vault, mint) in [(vault_a, mint_a), (vault_b, mint_b), (lp_lock, lp_mint)] {
+                    invoke_signed(
+                        &token_program::instruction::initialize_token_account(
+                            *token_program.key,
+                            *vault.key,
+                            *mint.key,
+                            *pool_ai.key,
+                        )?,
+                        &[
+                            token_program.clone(),
+                            vault.clone(),
+                            mint.clone(),
+                            pool_ai.clone(),
+                            token_config.clone(),
+                        ],
+                        &[],
+                    )?;
+                }
+
+                let pool = Pool {
+                    token_program: *token_program.key,
+                    mint_a: *mint_a.key,
+                    mint_b: *mint_b.key,
+                    vault_a: *vault_a.key,
+                    vault_b: *vault_b.key,
+                    lp_mint: *lp_mint.key,
+                    lp_lock: *lp_lock.key,
+                    fee_bps,
+                    bump,
+                };
+                pool.serialize(&mut *pool_ai.data.borrow_mut())?;
+            }
+            AmmInstruction::AddLiquidity {max_amount_a, max_amount_b, min_lp_amount} => {
+                msg!("Instruction: AddLiquidity");
+                let user = next_account_info(accounts_iter)?;
+                let pool_ai = next_account_info(accounts_iter)?;
+                let vault_a = next_account_info(accounts_iter)?;
+                let vault_b = next_account_info(accounts_iter)?;
+                let lp_mint = next_account_info(accounts_iter)?;
+                let user_token_a = next_account_info(accounts_iter)?;
+                let user_token_b = next_account_info(accounts_iter)?;
+                let user_lp = next_account_info(accounts_iter)?;
+                let mint_a = next_account_info(accounts_iter)?;
+                let mint_b = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let lp_lock = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                let pool = load_pool(program_id, pool_ai)?;
+                Self::assert_pool_accounts(&pool, vault_a, vault_b, lp_mint, mint_a, mint_b, token_program)?;
+                assert_with_msg(
+                    pool.lp_lock == *lp_lock.key,
+                    AmmError::PoolMismatch.into(),
+                    "Accounts do not match the pool"
+                )?;
+
+                let reserve_a = token_balance(vault_a)?;
+                let reserve_b = token_balance(vault_b)?;
+                let lp_supply = Mint::load(lp_mint)?.supply;
+
+                let (amount_a, amount_b, lp_amount) = if lp_supply == 0 {
+                    // First deposit sets the price, part of its LP tokens are locked for good
+                    let lp_amount = to_u64(isqrt(max_amount_a as u128 * max_amount_b as u128))?;
+                    assert_with_msg(
+                        lp_amount > MINIMUM_LIQUIDITY,
+                        AmmError::InsufficientInitialLiquidity.into(),
+                        "First deposit must mint more than the minimum liquidity"
+                    )?;
+                    (max_amount_a, max_amount_b, lp_amount - MINIMUM_LIQUIDITY)
+                } else {
+                    assert_with_msg(
+                        reserve_a > 0 && reserve_b > 0,
+                        AmmError::EmptyPool.into(),
+                        "Pool has no liquidity"
+                    )?;
+                    let lp_amount = std::cmp::min(
+                        mul_div(max_amount_a, lp_supply, reserve_a)?,
+                        mul_div(max_amount_b, lp_supply, reserve_b)?,
+                    );
+                    (
+                        mul_div_ceil(lp_amount, reserve_a, lp_supply)?,
+                        mul_div_ceil(lp_amount, reserve_b, lp_supply)?,
+                        lp_amount,
+                    )
+                };
+
+                assert_with_msg(
+                    lp_amount > 0 && lp_amount >= min_lp_amount,
+                    AmmError::SlippageExceeded.into(),
+                    "LP amount is below the minimum requested"
+                )?;
+
+                token_transfer(token_program, user_token_a, vault_a, user, mint_a, token_config, amount_a, &[])?;
+                token_transfer(token_program, user_token_b, vault_b, user, mint_b, token_config, amount_b, &[])?;
+                let pool_seeds: &[&[u8]] = &[POOL_SEED, pool.mint_a.as_ref(), pool.mint_b.as_ref(), &[pool.bump]];
+                if lp_supply == 0 {
+                    token_mint_to(token_program, lp_lock, lp_mint, pool_ai, token_config, MINIMUM_LIQUIDITY, pool_seeds)?;
+                }
+                token_mint_to(token_program, user_lp, lp_mint, pool_ai, token_config, lp_amount, pool_seeds)?;
+                msg!("Deposited {} A and {} B for {} LP", amount_a, amount_b, lp_amount);
+            }
+            AmmInstruction::RemoveLiquidity {lp_amount, min_amount_a, min_amount_b} => {
+                msg!("Instruction: RemoveLiquidity");
+                let user = next_account_info(accounts_iter)?;
+                let pool_ai = next_account_info(accounts_iter)?;
+                let vault_a = next_account_info(accounts_iter)?;
+                let vault_b = next_account_info(accounts_iter)?;
+                let lp_mint = next_account_info(accounts_iter)?;
+                let user_token_a = next_account_info(accounts_iter)?;
+                let user_token_b = next_account_info(accounts_iter)?;
+                let user_lp = next_account_info(accounts_iter)?;
+                let mint_a = next_account_info(accounts_iter)?;
+                let mint_b = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                let pool = load_pool(program_id, pool_ai)?;
+                Self::assert_pool_accounts(&pool, vault_a, vault_b, lp_mint, mint_a, mint_b, token_program)?;
+
+                let lp_supply = Mint::load(lp_mint)?.supply;
+                assert_with_msg(
+                    lp_supply > 0,
+                    AmmError::EmptyPool.into(),
+                    "Pool has no liquidity"
+                )?;
+                let amount_a = mul_div(lp_amount, token_balance(vault_a)?, lp_supply)?;
+                let amount_b = mul_div(lp_amount, token_balance(vault_b)?, lp_supply)?;
+                assert_with_msg(
+                    amount_a >= min_amount_a && amount_b >= min_amount_b,
+                    AmmError::SlippageExceeded.into(),
+                    "Withdrawn amounts are below the minimum requested"
+                )?;
+
+                // Burn is signed by the user, it fails if the user holds less than lp_amount
+                invoke_signed(
+                    &token_program::instruction::burn(
+                        *token_program.key,
+                        *user_lp.key,
+                        *lp_mint.key,
+                        *user.key,
+                        lp_amount,
+                    )?,
+                    &[token_program.clone(), user_lp.clone(), lp_mint.clone(), user.clone()],
+                    &[],
+                )?;
+
+                let pool_seeds: &[&[u8]] = &[POOL_SEED, pool.mint_a.as_ref(), pool.mint_b.as_ref(), &[pool.bump]];
+                token_transfer(token_program, vault_a, user_token_a, pool_ai, mint_a, token_config, amount_a, &[pool_seeds])?;
+                token_transfer(token_program, vault_b, user_token_b, pool_ai, mint_b, token_config, amount_b, &[pool_seeds])?;
+                msg!("Withdrew {} A and {} B for {} LP", amount_a, amount_b, lp_amount);
+            }
+            AmmInstruction::Swap {amount_in, min_amount_out} => {
+                msg!("Instruction: Swap");
+                let user = next_account_info(accounts_iter)?;
+                let pool_ai = next_account_info(accounts_iter)?;
+                let vault_in = next_account_info(accounts_iter)?;
+                let vault_out = next_account_info(accounts_iter)?;
+                let user_src = next_account_info(accounts_iter)?;
+                let user_dst = next_account_info(accounts_iter)?;
+                let mint_in = next_account_info(accounts_iter)?;
+                let mint_out = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                let pool = load_pool(program_id, pool_ai)?;
+                let a_to_b = *vault_in.key == pool.vault_a
+                    && *vault_out.key == pool.vault_b
+                    && *mint_in.key == pool.mint_a
+                    && *mint_out.key == pool.mint_b;
+                let b_to_a = *vault_in.key == pool.vault_b
+                    && *vault_out.key == pool.vault_a
+                    && *mint_in.key == pool.mint_b
+                    && *mint_out.key == pool.mint_a;
+                assert_with_msg(
+                    (a_to_b || b_to_a) && *token_program.key == pool.token_program,
+                    AmmError::PoolMismatch.into(),
+                    "Accounts do not match the pool"
+                )?;
+
+                let reserve_in = token_balance(vault_in)?;
+                let reserve_out = token_balance(vault_out)?;
+                assert_with_msg(
+                    reserve_in > 0 && reserve_out > 0,
+                    AmmError::EmptyPool.into(),
+                    "Pool has no liquidity"
+                )?;
+
+                let amount_out = swap_amount_out(amount_in, reserve_in, reserve_out, pool.fee_bps)?;
+                assert_with_msg(
+                    amount_out > 0 && amount_out >= min_amount_out,
+                    AmmError::SlippageExceeded.into(),
+                    "Output is below the minimum requested"
+                )?;
+
+                token_transfer(token_program, user_src, vault_in, user, mint_in, token_config, amount_in, &[])?;
+                token_transfer(
+                    token_program,
+                    vault_out,
+                    user_dst,
+                    pool_ai,
+                    mint_out,
+                    token_config,
+                    amount_out,
+                    &[&[POOL_SEED, pool.mint_a.as_ref(), pool.mint_b.as_ref(), &[pool.bump]]],
+                )?;
+                msg!("Swapped {} for {}", amount_in, amount_out);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn assert_pool_accounts(
+        pool: &Pool,
+        vault_a: &AccountInfo,
+        vault_b: &AccountInfo,
+        lp_mint: &AccountInfo,
+        mint_a: &AccountInfo,
+        mint_b: &AccountInfo,
+        token_program: &AccountInfo,
+    ) -> ProgramResult {
+        assert_with_msg(
+            pool.vault_a == *vault_a.key
+                && pool.vault_b == *vault_b.key
+                && pool.lp_mint == *lp_mint.key
+                && pool.mint_a == *mint_a.key
+                && pool.mint_b == *mint_b.key
+                && pool.token_program == *token_program.key,
+            AmmError::PoolMismatch.into(),
+            "Accounts do not match the pool"
+        )
+    }
+}
@@ -0,0 +1,35 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+pub const POOL_SEED: &[u8] = b"pool";
+pub const VAULT_SEED: &[u8] = b"vault";
+pub const LP_MINT_SEED: &[u8] = b"lp_mint";
+pub const LP_LOCK_SEED: &[u8] = b"lp_lock";
+
+pub const BASIS_POINTS: u64 = 10_000;
+// LP tokens minted to the lock account on the first deposit and never withdrawable.
+// Keeps the LP supply from going back to a few units, which would let the first
+// depositor inflate the price of one LP token and round later deposits down to nothing
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+/* A pool is a PDA seeded by [POOL_SEED, mint_a, mint_b], with mint_a < mint_b so that
+ there is a single pool per pair. The pool PDA owns both vaults, token_program TokenAccounts
+ seeded by [VAULT_SEED, pool, mint], and is the authority of the LP mint seeded by [LP_MINT_SEED, pool].
+ It also owns the LP lock, an LP TokenAccount seeded by [LP_LOCK_SEED, pool] that no instruction moves tokens out of.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Pool {
+    pub token_program: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub lp_mint: Pubkey,
+    pub lp_lock: Pubkey,
+    pub fee_bps: u16, // swap fee kept in the pool, in basis points of the input amount
+    pub bump: u8,
+}
+
+impl Pool {
+    pub const LEN: usize = 32 * 7 + 2 + 1;
+}
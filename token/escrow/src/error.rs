@@ -0,0 +1,16 @@
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum EscrowError {
+    #[error("Instruction not implemented.")]
+    NotImplemented,
+    #[error("Account does not match the escrow.")]
+    EscrowMismatch,
+}
+
+impl From<EscrowError> for ProgramError {
+    fn from(e: EscrowError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
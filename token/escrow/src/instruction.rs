@@ -0,0 +1,16 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum EscrowInstruction {
+    // Creates the escrow and its vault, then moves amount_a of mint A from the maker into the vault
+    // Accounts: [maker, escrow, vault, maker token account A, maker token account B, mint A,
+    //  token program, token program config, system program]
+    Make {seed: u64, amount_a: u64, expected_b: u64},
+    // Sends expected_b of mint B from the taker to the maker and the vault content to the taker
+    // Accounts: [taker, escrow, vault, taker token account B, taker token account A,
+    //  maker token account B, maker, mint A, mint B, token program, token program config]
+    Take,
+    // Returns the deposit to the maker
+    // Accounts: [maker, escrow, vault, maker token account A, mint A, token program, token program config]
+    Cancel,
+}
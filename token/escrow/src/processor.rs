@@ -0,0 +1,334 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{rent::Rent, Sysvar},
+};
+use token_program::state::TokenAccount;
+use token_program::utils::assert_token_program;
+
+use crate::error::EscrowError;
+use crate::instruction::EscrowInstruction;
+use crate::state::{Escrow, ESCROW_SEED, VAULT_SEED};
+
+pub fn assert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
+    if !statement {
+        msg!(msg);
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+// Drains the lamports of a program owned account and zeroes its data so it can't be reused
+fn close_account(ai: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let lamports = ai.lamports();
+    **destination.lamports.borrow_mut() = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    **ai.lamports.borrow_mut() = 0;
+    ai.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+/* The escrow never touches token balances itself, every token movement is a CPI
+   into token_program. Deposits are signed by the maker, withdrawals from the vault
+   are signed by the escrow PDA through invoke_signed.
+   The vault is left empty once the escrow is taken or cancelled, as token_program
+   has no instruction to close a token account and reclaim its rent.
+*/
+pub struct Processor {}
+
+impl Processor {
+    pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8]
+    ) -> ProgramResult {
+        let instruction = EscrowInstruction::try_from_slice(instruction_data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        let accounts_iter = &mut accounts.iter();
+
+        match instruction {
+            EscrowInstruction::Make {seed, amount_a, expected_b} => {
+                msg!("Instruction: Make");
+                let maker = next_account_info(accounts_iter)?;
+                let escrow_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let maker_token_a = next_account_info(accounts_iter)?;
+                let maker_token_b = next_account_info(accounts_iter)?;
+                let mint_a = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    maker.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Maker must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+
+                let seed_bytes = seed.to_le_bytes();
+                let (escrow_key, bump) = Pubkey::find_program_address(
+                    &[ESCROW_SEED, maker.key.as_ref(), &seed_bytes],
+                    program_id,
+                );
+                assert_with_msg(
+                    escrow_key == *escrow_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for escrow"
+                )?;
+                let (vault_key, vault_bump) = Pubkey::find_program_address(
+                    &[VAULT_SEED, escrow_key.as_ref()],
+                    program_id,
+                );
+                assert_with_msg(
+                    vault_key == *vault_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for vault"
+                )?;
+
+                // The maker receives mint B on this account once the escrow is taken
+                assert_with_msg(
+                    maker_token_b.owner == token_program.key,
+                    ProgramError::IncorrectProgramId,
+                    "Maker token account B must belong to the token program"
+                )?;
+                let maker_receive = TokenAccount::load(maker_token_b)?;
+                assert_with_msg(
+                    maker_receive.owner == *maker.key,
+                    EscrowError::EscrowMismatch.into(),
+                    "Maker token account B must be owned by the maker"
+                )?;
+
+                let rent = Rent::get()?;
+                invoke_signed(
+                    &system_instruction::create_account(
+                        maker.key,
+                        escrow_ai.key,
+                        rent.minimum_balance(Escrow::LEN),
+                        Escrow::LEN as u64,
+                        program_id,
+                    ),
+                    &[maker.clone(), escrow_ai.clone(), system_program.clone()],
+                    &[&[ESCROW_SEED, maker.key.as_ref(), &seed_bytes, &[bump]]],
+                )?;
+
+                // The vault is allocated by the escrow but owned by the token program
+                invoke_signed(
+                    &system_instruction::create_account(
+                        maker.key,
+                        vault_ai.key,
+                        rent.minimum_balance(TokenAccount::LEN),
+                        TokenAccount::LEN as u64,
+                        token_program.key,
+                    ),
+                    &[maker.clone(), vault_ai.clone(), system_program.clone()],
+                    &[&[VAULT_SEED, escrow_key.as_ref(), &[vault_bump]]],
+                )?;
+
+                invoke(
+                    &token_program::instruction::initialize_token_account(
+                        *token_program.key,
+                        *vault_ai.key,
+                        *mint_a.key,
+                        *escrow_ai.key, // the escrow PDA owns the deposit
+                    )?,
+                    &[
+                        token_program.clone(),
+                        vault_ai.clone(),
+                        mint_a.clone(),
+                        escrow_ai.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+
+                invoke(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *maker_token_a.key,
+                        *vault_ai.key,
+                        *maker.key,
+                        *mint_a.key,
+                        amount_a,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        maker_token_a.clone(),
+                        vault_ai.clone(),
+                        maker.clone(),
+                        mint_a.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+
+                let escrow = Escrow {
+                    maker: *maker.key,
+                    token_program: *token_program.key,
+                    mint_a: *mint_a.key,
+                    mint_b: maker_receive.mint,
+                    vault: *vault_ai.key,
+                    maker_receive: *maker_token_b.key,
+                    amount_a,
+                    expected_b,
+                    seed,
+                    bump,
+                    vault_bump,
+                };
+                escrow.serialize(&mut *escrow_ai.data.borrow_mut())?;
+            }
+            EscrowInstruction::Take => {
+                msg!("Instruction: Take");
+                let taker = next_account_info(accounts_iter)?;
+                let escrow_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let taker_token_b = next_account_info(accounts_iter)?;
+                let taker_token_a = next_account_info(accounts_iter)?;
+                let maker_token_b = next_account_info(accounts_iter)?;
+                let maker = next_account_info(accounts_iter)?;
+                let mint_a = next_account_info(accounts_iter)?;
+                let mint_b = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    taker.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Taker must sign."
+                )?;
+                assert_with_msg(
+                    escrow_ai.owner == program_id,
+                    ProgramError::IncorrectProgramId,
+                    "Escrow must be owned by the escrow program"
+                )?;
+                let escrow = Escrow::try_from_slice(&escrow_ai.data.borrow())?;
+                assert_with_msg(
+                    escrow.vault == *vault_ai.key
+                        && escrow.maker == *maker.key
+                        && escrow.maker_receive == *maker_token_b.key
+                        && escrow.mint_a == *mint_a.key
+                        && escrow.mint_b == *mint_b.key
+                        && escrow.token_program == *token_program.key,
+                    EscrowError::EscrowMismatch.into(),
+                    "Accounts do not match the escrow"
+                )?;
+
+                // Leg 1: the taker pays the maker, signed by the taker
+                invoke(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *taker_token_b.key,
+                        *maker_token_b.key,
+                        *taker.key,
+                        *mint_b.key,
+                        escrow.expected_b,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        taker_token_b.clone(),
+                        maker_token_b.clone(),
+                        taker.clone(),
+                        mint_b.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+
+                // Leg 2: the vault pays the taker, signed by the escrow PDA
+                let vault_amount = TokenAccount::load(vault_ai)?.amount;
+                invoke_signed(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *vault_ai.key,
+                        *taker_token_a.key,
+                        *escrow_ai.key,
+                        *mint_a.key,
+                        vault_amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        vault_ai.clone(),
+                        taker_token_a.clone(),
+                        escrow_ai.clone(),
+                        mint_a.clone(),
+                        token_config.clone(),
+                    ],
+                    &[&[ESCROW_SEED, escrow.maker.as_ref(), &escrow.seed.to_le_bytes(), &[escrow.bump]]],
+                )?;
+
+                // Rent of the escrow goes back to the maker who paid for it
+                close_account(escrow_ai, maker)?;
+            }
+            EscrowInstruction::Cancel => {
+                msg!("Instruction: Cancel");
+                let maker = next_account_info(accounts_iter)?;
+                let escrow_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let maker_token_a = next_account_info(accounts_iter)?;
+                let mint_a = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    maker.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Maker must sign."
+                )?;
+                assert_with_msg(
+                    escrow_ai.owner == program_id,
+                    ProgramError::IncorrectProgramId,
+                    "Escrow must be owned by the escrow program"
+                )?;
+                let escrow = Escrow::try_from_slice(&escrow_ai.data.borrow())?;
+                assert_with_msg(
+                    escrow.vault == *vault_ai.key
+                        && escrow.maker == *maker.key
+                        && escrow.mint_a == *mint_a.key
+                        && escrow.token_program == *token_program.key,
+                    EscrowError::EscrowMismatch.into(),
+                    "Accounts do not match the escrow"
+                )?;
+
+                let vault_amount = TokenAccount::load(vault_ai)?.amount;
+                invoke_signed(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *vault_ai.key,
+                        *maker_token_a.key,
+                        *escrow_ai.key,
+                        *mint_a.key,
+                        vault_amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        vault_ai.clone(),
+                        maker_token_a.clone(),
+                        escrow_ai.clone(),
+                        mint_a.clone(),
+                        token_config.clone(),
+                    ],
+                    &[&[ESCROW_SEED, escrow.maker.as_ref(), &escrow.seed.to_le_bytes(), &[escrow.bump]]],
+                )?;
+
+                close_account(escrow_ai, maker)?;
+            }
+        }
+
+        Ok(())
+    }
+}
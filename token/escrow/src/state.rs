@@ -0,0 +1,28 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+pub const ESCROW_SEED: &[u8] = b"escrow";
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/* An escrow is a PDA seeded by [ESCROW_SEED, maker, seed].
+ It owns the vault, a token_program TokenAccount of mint_a that is itself a PDA
+ seeded by [VAULT_SEED, escrow], holding the maker's deposit until the swap is taken or cancelled.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Escrow {
+    pub maker: Pubkey,
+    pub token_program: Pubkey, // token program the vault and every token account belong to
+    pub mint_a: Pubkey, // mint deposited by the maker
+    pub mint_b: Pubkey, // mint expected in return
+    pub vault: Pubkey,
+    pub maker_receive: Pubkey, // maker's TokenAccount of mint_b
+    pub amount_a: u64,
+    pub expected_b: u64,
+    pub seed: u64, // lets a maker have several open escrows
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+impl Escrow {
+    pub const LEN: usize = 32 * 6 + 8 * 3 + 1 + 1;
+}
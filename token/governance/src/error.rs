@@ -0,0 +1,30 @@
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum GovernanceError {
+    #[error("Instruction not implemented.")]
+    NotImplemented,
+    #[error("Account does not match the realm.")]
+    RealmMismatch,
+    #[error("Not enough governing tokens deposited.")]
+    NotEnoughTokens,
+    #[error("Voting has ended.")]
+    VotingEnded,
+    #[error("Voting has not ended.")]
+    VotingNotEnded,
+    #[error("Proposal did not pass.")]
+    ProposalNotPassed,
+    #[error("Proposal already executed.")]
+    AlreadyExecuted,
+    #[error("Tokens are locked until the end of the proposals voted on.")]
+    TokensLocked,
+    #[error("Numerical overflow.")]
+    MathOverflow,
+}
+
+impl From<GovernanceError> for ProgramError {
+    fn from(e: GovernanceError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
@@ -0,0 +1,29 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::state::StoredInstruction;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum GovernanceInstruction {
+    // Accounts: [creator, realm, governing mint, token program, system program]
+    CreateRealm {
+        id: u64,
+        voting_period: i64,
+        quorum: u64,
+        min_tokens_to_propose: u64,
+    },
+    // Creates the voter record and vault on first use
+    // Accounts: [owner, realm, voter record, voter vault, owner token account, governing mint,
+    //  token program, token program config, system program]
+    DepositGoverningTokens {amount: u64},
+    // Accounts: [owner, realm, voter record, voter vault, owner token account, governing mint,
+    //  token program, token program config]
+    WithdrawGoverningTokens {amount: u64},
+    // Accounts: [proposer, realm, proposal, voter record, voter vault, system program]
+    CreateProposal {instructions: Vec<StoredInstruction>},
+    // Accounts: [voter, realm, proposal, voter record, voter vault, vote record, system program]
+    CastVote {approve: bool},
+    // Can be called by anyone once a proposal passed
+    // Accounts: [realm, proposal, governance, ...every account used by the stored instructions,
+    //  including the invoked programs]
+    ExecuteProposal,
+}
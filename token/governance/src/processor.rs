@@ -0,0 +1,532 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{clock::Clock, Sysvar},
+};
+use token_program::state::{Mint, TokenAccount};
+use token_program::utils::{assert_token_program, create_pda_account};
+
+use crate::error::GovernanceError;
+use crate::instruction::GovernanceInstruction;
+use crate::state::{
+    Proposal, Realm, VoteRecord, VoterRecord, GOVERNANCE_SEED, PROPOSAL_SEED, REALM_SEED,
+    VOTER_SEED, VOTER_VAULT_SEED, VOTE_SEED,
+};
+
+pub fn assert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
+    if !statement {
+        msg!(msg);
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+fn load_realm(program_id: &Pubkey, realm_ai: &AccountInfo) -> Result<Realm, ProgramError> {
+    assert_with_msg(
+        realm_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Realm must be owned by the governance program"
+    )?;
+    Ok(Realm::try_from_slice(&realm_ai.data.borrow())?)
+}
+
+fn load_proposal(program_id: &Pubkey, proposal_ai: &AccountInfo, realm_ai: &AccountInfo) -> Result<Proposal, ProgramError> {
+    assert_with_msg(
+        proposal_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Proposal must be owned by the governance program"
+    )?;
+    let proposal = Proposal::try_from_slice(&proposal_ai.data.borrow())?;
+    assert_with_msg(
+        proposal.realm == *realm_ai.key,
+        GovernanceError::RealmMismatch.into(),
+        "Proposal does not belong to the realm"
+    )?;
+    Ok(proposal)
+}
+
+fn load_voter_record(
+    program_id: &Pubkey,
+    voter_record_ai: &AccountInfo,
+    realm_ai: &AccountInfo,
+    owner: &AccountInfo,
+) -> Result<VoterRecord, ProgramError> {
+    assert_with_msg(
+        voter_record_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Voter record must be owned by the governance program"
+    )?;
+    let voter_record = VoterRecord::try_from_slice(&voter_record_ai.data.borrow())?;
+    assert_with_msg(
+        voter_record.realm == *realm_ai.key && voter_record.owner == *owner.key,
+        GovernanceError::RealmMismatch.into(),
+        "Voter record does not match the realm and owner"
+    )?;
+    Ok(voter_record)
+}
+
+// Voting weight is the amount held in the voter vault
+fn voter_weight(voter_record: &VoterRecord, voter_vault: &AccountInfo) -> Result<u64, ProgramError> {
+    assert_with_msg(
+        voter_record.vault == *voter_vault.key,
+        GovernanceError::RealmMismatch.into(),
+        "Voter vault does not match the voter record"
+    )?;
+    Ok(TokenAccount::load(voter_vault)?.amount)
+}
+
+pub struct Processor {}
+
+impl Processor {
+    pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8]
+    ) -> ProgramResult {
+        let instruction = GovernanceInstruction::try_from_slice(instruction_data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        let accounts_iter = &mut accounts.iter();
+
+        match instruction {
+            GovernanceInstruction::CreateRealm {id, voting_period, quorum, min_tokens_to_propose} => {
+                msg!("Instruction: CreateRealm");
+                let creator = next_account_info(accounts_iter)?;
+                let realm_ai = next_account_info(accounts_iter)?;
+                let governing_mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    creator.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Creator must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                assert_with_msg(
+                    voting_period > 0,
+                    ProgramError::InvalidArgument,
+                    "Voting period must be positive"
+                )?;
+                assert_with_msg(
+                    governing_mint.owner == token_program.key,
+                    ProgramError::IncorrectProgramId,
+                    "Governing mint must belong to the token program"
+                )?;
+                Mint::load(governing_mint)?;
+
+                let id_bytes = id.to_le_bytes();
+                let (realm_key, bump) = Pubkey::find_program_address(
+                    &[REALM_SEED, creator.key.as_ref(), &id_bytes],
+                    program_id,
+                );
+                assert_with_msg(
+                    realm_key == *realm_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for realm"
+                )?;
+                let (governance_key, governance_bump) = Pubkey::find_program_address(
+                    &[GOVERNANCE_SEED, realm_key.as_ref()],
+                    program_id,
+                );
+
+                create_pda_account(
+                    creator,
+                    realm_ai,
+                    Realm::LEN,
+                    program_id,
+                    system_program,
+                    &[REALM_SEED, creator.key.as_ref(), &id_bytes, &[bump]],
+                )?;
+
+                let realm = Realm {
+                    creator: *creator.key,
+                    id,
+                    token_program: *token_program.key,
+                    governing_mint: *governing_mint.key,
+                    voting_period,
+                    quorum,
+                    min_tokens_to_propose,
+                    proposal_count: 0,
+                    bump,
+                    governance_bump,
+                };
+                // Authorities to hand over to the realm, e.g. with token_program SetMintAuthority
+                msg!("Governance authority {}", governance_key);
+                realm.serialize(&mut *realm_ai.data.borrow_mut())?;
+            }
+            GovernanceInstruction::DepositGoverningTokens {amount} => {
+                msg!("Instruction: DepositGoverningTokens");
+                let owner = next_account_info(accounts_iter)?;
+                let realm_ai = next_account_info(accounts_iter)?;
+                let voter_record_ai = next_account_info(accounts_iter)?;
+                let voter_vault = next_account_info(accounts_iter)?;
+                let owner_token = next_account_info(accounts_iter)?;
+                let governing_mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    owner.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Owner must sign."
+                )?;
+                let realm = load_realm(program_id, realm_ai)?;
+                assert_with_msg(
+                    realm.governing_mint == *governing_mint.key && realm.token_program == *token_program.key,
+                    GovernanceError::RealmMismatch.into(),
+                    "Accounts do not match the realm"
+                )?;
+
+                // First deposit of this owner in this realm
+                if voter_record_ai.data_is_empty() {
+                    assert_with_msg(
+                        *system_program.key == SYSTEM_PROGRAM_ID,
+                        ProgramError::InvalidArgument,
+                        "Invalid passed in for system programs"
+                    )?;
+                    let (voter_record_key, voter_record_bump) = Pubkey::find_program_address(
+                        &[VOTER_SEED, realm_ai.key.as_ref(), owner.key.as_ref()],
+                        program_id,
+                    );
+                    let (voter_vault_key, voter_vault_bump) = Pubkey::find_program_address(
+                        &[VOTER_VAULT_SEED, realm_ai.key.as_ref(), owner.key.as_ref()],
+                        program_id,
+                    );
+                    assert_with_msg(
+                        voter_record_key == *voter_record_ai.key && voter_vault_key == *voter_vault.key,
+                        ProgramError::InvalidArgument,
+                        "Invalid PDA seeds for voter accounts"
+                    )?;
+                    create_pda_account(
+                        owner,
+                        voter_record_ai,
+                        VoterRecord::LEN,
+                        program_id,
+                        system_program,
+                        &[VOTER_SEED, realm_ai.key.as_ref(), owner.key.as_ref(), &[voter_record_bump]],
+                    )?;
+                    create_pda_account(
+                        owner,
+                        voter_vault,
+                        TokenAccount::LEN,
+                        token_program.key,
+                        system_program,
+                        &[VOTER_VAULT_SEED, realm_ai.key.as_ref(), owner.key.as_ref(), &[voter_vault_bump]],
+                    )?;
+                    invoke(
+                        &token_program::instruction::initialize_token_account(
+                            *token_program.key,
+                            *voter_vault.key,
+                            *governing_mint.key,
+                            *realm_ai.key,
+                        )?,
+                        &[
+                            token_program.clone(),
+                            voter_vault.clone(),
+                            governing_mint.clone(),
+                            realm_ai.clone(),
+                            token_config.clone(),
+                        ],
+                    )?;
+                    let voter_record = VoterRecord {
+                        realm: *realm_ai.key,
+                        owner: *owner.key,
+                        vault: *voter_vault.key,
+                        locked_until: 0,
+                        bump: voter_record_bump,
+                    };
+                    voter_record.serialize(&mut *voter_record_ai.data.borrow_mut())?;
+                }
+                let voter_record = load_voter_record(program_id, voter_record_ai, realm_ai, owner)?;
+                assert_with_msg(
+                    voter_record.vault == *voter_vault.key,
+                    GovernanceError::RealmMismatch.into(),
+                    "Voter vault does not match the voter record"
+                )?;
+
+                invoke(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *owner_token.key,
+                        *voter_vault.key,
+                        *owner.key,
+                        *governing_mint.key,
+                        amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        owner_token.clone(),
+                        voter_vault.clone(),
+                        owner.clone(),
+                        governing_mint.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+            }
+            GovernanceInstruction::WithdrawGoverningTokens {amount} => {
+                msg!("Instruction: WithdrawGoverningTokens");
+                let owner = next_account_info(accounts_iter)?;
+                let realm_ai = next_account_info(accounts_iter)?;
+                let voter_record_ai = next_account_info(accounts_iter)?;
+                let voter_vault = next_account_info(accounts_iter)?;
+                let owner_token = next_account_info(accounts_iter)?;
+                let governing_mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    owner.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Owner must sign."
+                )?;
+                let realm = load_realm(program_id, realm_ai)?;
+                assert_with_msg(
+                    realm.governing_mint == *governing_mint.key && realm.token_program == *token_program.key,
+                    GovernanceError::RealmMismatch.into(),
+                    "Accounts do not match the realm"
+                )?;
+                let voter_record = load_voter_record(program_id, voter_record_ai, realm_ai, owner)?;
+                assert_with_msg(
+                    voter_record.vault == *voter_vault.key,
+                    GovernanceError::RealmMismatch.into(),
+                    "Voter vault does not match the voter record"
+                )?;
+                assert_with_msg(
+                    Clock::get()?.unix_timestamp >= voter_record.locked_until,
+                    GovernanceError::TokensLocked.into(),
+                    "Tokens are locked until the end of the proposals voted on"
+                )?;
+
+                // The realm PDA owns the voter vaults
+                invoke_signed(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *voter_vault.key,
+                        *owner_token.key,
+                        *realm_ai.key,
+                        *governing_mint.key,
+                        amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        voter_vault.clone(),
+                        owner_token.clone(),
+                        realm_ai.clone(),
+                        governing_mint.clone(),
+                        token_config.clone(),
+                    ],
+                    &[&[REALM_SEED, realm.creator.as_ref(), &realm.id.to_le_bytes(), &[realm.bump]]],
+                )?;
+            }
+            GovernanceInstruction::CreateProposal {instructions} => {
+                msg!("Instruction: CreateProposal");
+                let proposer = next_account_info(accounts_iter)?;
+                let realm_ai = next_account_info(accounts_iter)?;
+                let proposal_ai = next_account_info(accounts_iter)?;
+                let voter_record_ai = next_account_info(accounts_iter)?;
+                let voter_vault = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+
+                assert_with_msg(
+                    proposer.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Proposer must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                let mut realm = load_realm(program_id, realm_ai)?;
+                let voter_record = load_voter_record(program_id, voter_record_ai, realm_ai, proposer)?;
+                assert_with_msg(
+                    voter_weight(&voter_record, voter_vault)? >= realm.min_tokens_to_propose,
+                    GovernanceError::NotEnoughTokens.into(),
+                    "Not enough governing tokens deposited to propose"
+                )?;
+
+                let index = realm.proposal_count;
+                let index_bytes = index.to_le_bytes();
+                let (proposal_key, proposal_bump) = Pubkey::find_program_address(
+                    &[PROPOSAL_SEED, realm_ai.key.as_ref(), &index_bytes],
+                    program_id,
+                );
+                assert_with_msg(
+                    proposal_key == *proposal_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for proposal"
+                )?;
+
+                let proposal = Proposal {
+                    realm: *realm_ai.key,
+                    proposer: *proposer.key,
+                    index,
+                    instructions,
+                    yes_votes: 0,
+                    no_votes: 0,
+                    voting_ends_at: Clock::get()?
+                        .unix_timestamp
+                        .checked_add(realm.voting_period)
+                        .ok_or(GovernanceError::MathOverflow)?,
+                    executed: false,
+                    bump: proposal_bump,
+                };
+                // The account is sized to fit exactly the stored instructions
+                let data = proposal.try_to_vec()?;
+                create_pda_account(
+                    proposer,
+                    proposal_ai,
+                    data.len(),
+                    program_id,
+                    system_program,
+                    &[PROPOSAL_SEED, realm_ai.key.as_ref(), &index_bytes, &[proposal_bump]],
+                )?;
+                proposal_ai.data.borrow_mut().copy_from_slice(&data);
+
+                realm.proposal_count += 1;
+                msg!("Proposal {} voting ends at {}", index, proposal.voting_ends_at);
+                realm.serialize(&mut *realm_ai.data.borrow_mut())?;
+            }
+            GovernanceInstruction::CastVote {approve} => {
+                msg!("Instruction: CastVote");
+                let voter = next_account_info(accounts_iter)?;
+                let realm_ai = next_account_info(accounts_iter)?;
+                let proposal_ai = next_account_info(accounts_iter)?;
+                let voter_record_ai = next_account_info(accounts_iter)?;
+                let voter_vault = next_account_info(accounts_iter)?;
+                let vote_record_ai = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+
+                assert_with_msg(
+                    voter.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Voter must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                load_realm(program_id, realm_ai)?;
+                let mut proposal = load_proposal(program_id, proposal_ai, realm_ai)?;
+                assert_with_msg(
+                    Clock::get()?.unix_timestamp < proposal.voting_ends_at,
+                    GovernanceError::VotingEnded.into(),
+                    "Voting has ended"
+                )?;
+                let mut voter_record = load_voter_record(program_id, voter_record_ai, realm_ai, voter)?;
+                let weight = voter_weight(&voter_record, voter_vault)?;
+                assert_with_msg(
+                    weight > 0,
+                    GovernanceError::NotEnoughTokens.into(),
+                    "No governing tokens deposited"
+                )?;
+
+                // Creating the vote record fails if the voter already voted
+                let (vote_record_key, vote_record_bump) = Pubkey::find_program_address(
+                    &[VOTE_SEED, proposal_ai.key.as_ref(), voter.key.as_ref()],
+                    program_id,
+                );
+                assert_with_msg(
+                    vote_record_key == *vote_record_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for vote record"
+                )?;
+                create_pda_account(
+                    voter,
+                    vote_record_ai,
+                    VoteRecord::LEN,
+                    program_id,
+                    system_program,
+                    &[VOTE_SEED, proposal_ai.key.as_ref(), voter.key.as_ref(), &[vote_record_bump]],
+                )?;
+                let vote_record = VoteRecord {
+                    proposal: *proposal_ai.key,
+                    voter: *voter.key,
+                    approve,
+                    weight,
+                };
+                vote_record.serialize(&mut *vote_record_ai.data.borrow_mut())?;
+
+                if approve {
+                    proposal.yes_votes = proposal.yes_votes.checked_add(weight).ok_or(GovernanceError::MathOverflow)?;
+                } else {
+                    proposal.no_votes = proposal.no_votes.checked_add(weight).ok_or(GovernanceError::MathOverflow)?;
+                }
+                // The deposit can't be withdrawn and used to vote again before the proposal ends
+                voter_record.locked_until = voter_record.locked_until.max(proposal.voting_ends_at);
+
+                msg!("Voted {} with weight {}", if approve { "yes" } else { "no" }, weight);
+                voter_record.serialize(&mut *voter_record_ai.data.borrow_mut())?;
+                proposal.serialize(&mut *proposal_ai.data.borrow_mut())?;
+            }
+            GovernanceInstruction::ExecuteProposal => {
+                msg!("Instruction: ExecuteProposal");
+                let realm_ai = next_account_info(accounts_iter)?;
+                let proposal_ai = next_account_info(accounts_iter)?;
+                let governance_ai = next_account_info(accounts_iter)?;
+                let remaining_accounts = accounts_iter.as_slice();
+
+                let realm = load_realm(program_id, realm_ai)?;
+                let mut proposal = load_proposal(program_id, proposal_ai, realm_ai)?;
+                assert_with_msg(
+                    Clock::get()?.unix_timestamp >= proposal.voting_ends_at,
+                    GovernanceError::VotingNotEnded.into(),
+                    "Voting has not ended"
+                )?;
+                assert_with_msg(
+                    proposal.passed(realm.quorum),
+                    GovernanceError::ProposalNotPassed.into(),
+                    "Proposal did not pass"
+                )?;
+                assert_with_msg(
+                    !proposal.executed,
+                    GovernanceError::AlreadyExecuted.into(),
+                    "Proposal already executed"
+                )?;
+
+                let governance_seeds: &[&[u8]] = &[GOVERNANCE_SEED, realm_ai.key.as_ref(), &[realm.governance_bump]];
+                let governance_key = Pubkey::create_program_address(governance_seeds, program_id)?;
+                assert_with_msg(
+                    governance_key == *governance_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for governance"
+                )?;
+
+                // Mark as executed first, in case a stored instruction calls back into this program
+                proposal.executed = true;
+                proposal.serialize(&mut *proposal_ai.data.borrow_mut())?;
+
+                let mut account_infos = remaining_accounts.to_vec();
+                account_infos.push(governance_ai.clone());
+                for stored in &proposal.instructions {
+                    invoke_signed(
+                        &Instruction::from(stored),
+                        &account_infos,
+                        &[governance_seeds],
+                    )?;
+                }
+                msg!("Executed proposal {}", proposal.index);
+            }
+        }
+
+        Ok(())
+    }
+}
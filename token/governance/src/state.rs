@@ -0,0 +1,119 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+pub const REALM_SEED: &[u8] = b"realm";
+pub const GOVERNANCE_SEED: &[u8] = b"governance";
+pub const VOTER_SEED: &[u8] = b"voter";
+pub const VOTER_VAULT_SEED: &[u8] = b"voter_vault";
+pub const PROPOSAL_SEED: &[u8] = b"proposal";
+pub const VOTE_SEED: &[u8] = b"vote";
+
+/* A realm is a PDA seeded by [REALM_SEED, creator, id], governed by the holders of governing_mint.
+ Holders deposit their tokens in a voter vault, a token_program TokenAccount seeded by
+ [VOTER_VAULT_SEED, realm, owner] and owned by the realm PDA. The vault amount is the voting weight,
+ and tokens stay locked until every proposal voted on has ended, so they can't vote twice.
+ Passed proposals are executed with the signature of the governance PDA seeded by [GOVERNANCE_SEED, realm],
+ which is meant to hold authorities such as Mint::authority or authorized_counter's Counter::authority,
+ handed over with token_program SetMintAuthority or authorized_counter TransferAuthority.
+ The governance PDA is distinct from the realm so that proposals can't move deposited tokens.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Realm {
+    pub creator: Pubkey,
+    pub id: u64,
+    pub token_program: Pubkey,
+    pub governing_mint: Pubkey,
+    pub voting_period: i64, // seconds
+    pub quorum: u64, // minimum yes weight for a proposal to pass
+    pub min_tokens_to_propose: u64,
+    pub proposal_count: u64,
+    pub bump: u8,
+    pub governance_bump: u8,
+}
+
+impl Realm {
+    pub const LEN: usize = 32 + 8 + 32 * 2 + 8 * 4 + 1 + 1;
+}
+
+// Per voter and realm, seeded by [VOTER_SEED, realm, owner]
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct VoterRecord {
+    pub realm: Pubkey,
+    pub owner: Pubkey,
+    pub vault: Pubkey,
+    pub locked_until: i64, // end of the last proposal voted on
+    pub bump: u8,
+}
+
+impl VoterRecord {
+    pub const LEN: usize = 32 * 3 + 8 + 1;
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct StoredAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+// Instruction executed by the governance PDA once a proposal passes
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct StoredInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<StoredAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl From<&StoredInstruction> for Instruction {
+    fn from(ix: &StoredInstruction) -> Self {
+        Instruction {
+            program_id: ix.program_id,
+            accounts: ix
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: ix.data.clone(),
+        }
+    }
+}
+
+// Seeded by [PROPOSAL_SEED, realm, index]. Its size depends on the stored instructions
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Proposal {
+    pub realm: Pubkey,
+    pub proposer: Pubkey,
+    pub index: u64,
+    pub instructions: Vec<StoredInstruction>,
+    pub yes_votes: u64,
+    pub no_votes: u64,
+    pub voting_ends_at: i64,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+impl Proposal {
+    pub fn passed(&self, quorum: u64) -> bool {
+        self.yes_votes > self.no_votes && self.yes_votes >= quorum
+    }
+}
+
+// Seeded by [VOTE_SEED, proposal, voter], its existence prevents voting twice
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub approve: bool,
+    pub weight: u64,
+}
+
+impl VoteRecord {
+    pub const LEN: usize = 32 * 2 + 1 + 8;
+}
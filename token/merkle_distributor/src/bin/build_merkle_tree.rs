@@ -0,0 +1,83 @@
+/* Off-chain helper building the airdrop merkle tree.
+ Input: a CSV file with one `address,amount` line per recipient (an optional header line is skipped).
+ Output: the merkle root, the total and the number of recipients to pass to InitializeDistributor,
+ followed by one `index,address,amount,proof` line per recipient, proof being the
+ hex encoded sibling hashes separated by `:`, to pass to Claim.
+
+ Usage: cargo run --features tree-builder --bin build_merkle_tree -- recipients.csv
+*/
+use std::{env, fs, process, str::FromStr};
+
+use merkle_distributor::merkle::{leaf_hash, MerkleTree};
+use solana_program::pubkey::Pubkey;
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn parse_recipients(csv: &str) -> Result<Vec<(Pubkey, u64)>, String> {
+    let mut recipients = vec![];
+    for (line_number, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (address, amount) = line
+            .split_once(',')
+            .ok_or(format!("line {}: expected `address,amount`", line_number + 1))?;
+        let address = match Pubkey::from_str(address.trim()) {
+            Ok(address) => address,
+            Err(_) if line_number == 0 => continue, // header
+            Err(e) => return Err(format!("line {}: invalid address: {}", line_number + 1, e)),
+        };
+        let amount = amount
+            .trim()
+            .parse::<u64>()
+            .map_err(|e| format!("line {}: invalid amount: {}", line_number + 1, e))?;
+        recipients.push((address, amount));
+    }
+    Ok(recipients)
+}
+
+fn main() {
+    let path = env::args().nth(1).unwrap_or_else(|| {
+        eprintln!("usage: build_merkle_tree <recipients.csv>");
+        process::exit(1);
+    });
+    let csv = fs::read_to_string(&path).unwrap_or_else(|e| {
+        eprintln!("cannot read {}: {}", path, e);
+        process::exit(1);
+    });
+    let recipients = parse_recipients(&csv).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
+    let leaves = recipients
+        .iter()
+        .enumerate()
+        .map(|(index, (address, amount))| leaf_hash(index as u64, address, *amount))
+        .collect();
+    let tree = MerkleTree::new(leaves);
+    let total = recipients
+        .iter()
+        .try_fold(0u64, |total, (_, amount)| total.checked_add(*amount))
+        .unwrap_or_else(|| {
+            eprintln!("total amount overflows u64");
+            process::exit(1);
+        });
+
+    println!("root: {}", to_hex(&tree.root()));
+    println!("total: {}", total);
+    println!("recipients: {}", recipients.len());
+    println!("index,address,amount,proof");
+    for (index, (address, amount)) in recipients.iter().enumerate() {
+        let proof = tree
+            .proof(index)
+            .iter()
+            .map(|node| to_hex(node))
+            .collect::<Vec<_>>()
+            .join(":");
+        println!("{},{},{},{}", index, address, amount, proof);
+    }
+}
@@ -0,0 +1,24 @@
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum DistributorError {
+    #[error("Instruction not implemented.")]
+    NotImplemented,
+    #[error("Account does not match the distributor.")]
+    DistributorMismatch,
+    #[error("Invalid merkle proof.")]
+    InvalidProof,
+    #[error("Index already claimed.")]
+    AlreadyClaimed,
+    #[error("Index out of range.")]
+    IndexOutOfRange,
+    #[error("Claims exceed the airdrop total.")]
+    ExceedsTotal,
+}
+
+impl From<DistributorError> for ProgramError {
+    fn from(e: DistributorError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
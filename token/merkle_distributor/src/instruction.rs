@@ -0,0 +1,18 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum DistributorInstruction {
+    // Creates the distributor and its vault, then funds the vault with total_amount from the creator
+    // Accounts: [creator, distributor, vault, mint, creator token account, token program,
+    //  token program config, system program]
+    InitializeDistributor {
+        id: u64,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        num_recipients: u64,
+    },
+    // proof is the list of sibling hashes from the leaf up to the root, see merkle.rs
+    // Accounts: [claimant, distributor, claim bitmap chunk, vault, claimant token account, mint,
+    //  token program, token program config, system program]
+    Claim {index: u64, amount: u64, proof: Vec<[u8; 32]>},
+}
@@ -0,0 +1,6 @@
+pub mod entrypoint;
+pub mod error;
+pub mod instruction;
+pub mod merkle;
+pub mod processor;
+pub mod state;
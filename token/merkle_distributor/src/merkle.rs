@@ -0,0 +1,126 @@
+use solana_program::{keccak::hashv, pubkey::Pubkey};
+
+/* Merkle tree shared by the program, which verifies proofs, and the off-chain
+ builder in src/bin/build_merkle_tree.rs, which computes the root and the proofs.
+ Leaves and inner nodes are hashed with different prefixes so that an inner node
+ can never be passed off as a leaf. Pairs are sorted before hashing, so a proof
+ is just the list of siblings, without left/right flags.
+*/
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub fn leaf_hash(index: u64, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &index.to_le_bytes(),
+        claimant.as_ref(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| hash_pair(&node, sibling));
+    computed == *root
+}
+
+// Off-chain tree builder, only compiled with the tree-builder feature so that it stays out of the program.
+// A node without a sibling is promoted to the next level as is
+#[cfg(any(test, feature = "tree-builder"))]
+pub struct MerkleTree {
+    levels: Vec<Vec<[u8; 32]>>, // levels[0] holds the leaves, the last level holds the root
+}
+
+#[cfg(any(test, feature = "tree-builder"))]
+impl MerkleTree {
+    pub fn new(leaves: Vec<[u8; 32]>) -> Self {
+        let mut levels = vec![leaves];
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        Self {levels}
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn proof(&self, mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = vec![];
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: u64) -> Vec<(Pubkey, u64, [u8; 32])> {
+        (0..count)
+            .map(|index| {
+                let claimant = Pubkey::new_unique();
+                let amount = 100 * (index + 1);
+                (claimant, amount, leaf_hash(index, &claimant, amount))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_proof_verifies() {
+        // Odd counts exercise the nodes promoted without a sibling
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let tree = MerkleTree::new(leaves.iter().map(|(_, _, leaf)| *leaf).collect());
+            for (index, (_, _, leaf)) in leaves.iter().enumerate() {
+                assert!(verify(&tree.proof(index), &tree.root(), *leaf), "leaf {} of {}", index, count);
+            }
+        }
+    }
+
+    #[test]
+    fn single_leaf_is_the_root() {
+        let leaves = leaves(1);
+        let tree = MerkleTree::new(vec![leaves[0].2]);
+        assert_eq!(tree.root(), leaves[0].2);
+        assert!(tree.proof(0).is_empty());
+    }
+
+    #[test]
+    fn tampered_claims_do_not_verify() {
+        let leaves = leaves(5);
+        let tree = MerkleTree::new(leaves.iter().map(|(_, _, leaf)| *leaf).collect());
+        let (claimant, amount, _) = leaves[2];
+        let proof = tree.proof(2);
+
+        assert!(verify(&proof, &tree.root(), leaf_hash(2, &claimant, amount)));
+        assert!(!verify(&proof, &tree.root(), leaf_hash(2, &claimant, amount + 1)));
+        assert!(!verify(&proof, &tree.root(), leaf_hash(3, &claimant, amount)));
+        assert!(!verify(&proof, &tree.root(), leaf_hash(2, &Pubkey::new_unique(), amount)));
+        assert!(!verify(&proof[1..], &tree.root(), leaf_hash(2, &claimant, amount)));
+    }
+}
@@ -0,0 +1,271 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+};
+use token_program::state::TokenAccount;
+use token_program::utils::{assert_token_program, create_pda_account};
+
+use crate::error::DistributorError;
+use crate::instruction::DistributorInstruction;
+use crate::merkle;
+use crate::state::{
+    Distributor, CLAIMS_SEED, CLAIM_CHUNK_BITS, CLAIM_CHUNK_BYTES, DISTRIBUTOR_SEED, VAULT_SEED,
+};
+
+pub fn assert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
+    if !statement {
+        msg!(msg);
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+pub struct Processor {}
+
+impl Processor {
+    pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8]
+    ) -> ProgramResult {
+        let instruction = DistributorInstruction::try_from_slice(instruction_data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        let accounts_iter = &mut accounts.iter();
+
+        match instruction {
+            DistributorInstruction::InitializeDistributor {id, merkle_root, total_amount, num_recipients} => {
+                msg!("Instruction: InitializeDistributor");
+                let creator = next_account_info(accounts_iter)?;
+                let distributor_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let mint = next_account_info(accounts_iter)?;
+                let creator_token = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    creator.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Creator must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+
+                let id_bytes = id.to_le_bytes();
+                let (distributor_key, bump) = Pubkey::find_program_address(
+                    &[DISTRIBUTOR_SEED, creator.key.as_ref(), &id_bytes],
+                    program_id,
+                );
+                let (vault_key, vault_bump) = Pubkey::find_program_address(
+                    &[VAULT_SEED, distributor_key.as_ref()],
+                    program_id,
+                );
+                assert_with_msg(
+                    distributor_key == *distributor_ai.key && vault_key == *vault_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for distributor accounts"
+                )?;
+
+                create_pda_account(
+                    creator,
+                    distributor_ai,
+                    Distributor::LEN,
+                    program_id,
+                    system_program,
+                    &[DISTRIBUTOR_SEED, creator.key.as_ref(), &id_bytes, &[bump]],
+                )?;
+                create_pda_account(
+                    creator,
+                    vault_ai,
+                    TokenAccount::LEN,
+                    token_program.key,
+                    system_program,
+                    &[VAULT_SEED, distributor_key.as_ref(), &[vault_bump]],
+                )?;
+                invoke(
+                    &token_program::instruction::initialize_token_account(
+                        *token_program.key,
+                        *vault_ai.key,
+                        *mint.key,
+                        *distributor_ai.key,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        vault_ai.clone(),
+                        mint.clone(),
+                        distributor_ai.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+                invoke(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *creator_token.key,
+                        *vault_ai.key,
+                        *creator.key,
+                        *mint.key,
+                        total_amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        creator_token.clone(),
+                        vault_ai.clone(),
+                        creator.clone(),
+                        mint.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+
+                let distributor = Distributor {
+                    creator: *creator.key,
+                    id,
+                    token_program: *token_program.key,
+                    mint: *mint.key,
+                    vault: *vault_ai.key,
+                    merkle_root,
+                    total_amount,
+                    total_claimed: 0,
+                    num_recipients,
+                    bump,
+                };
+                distributor.serialize(&mut *distributor_ai.data.borrow_mut())?;
+            }
+            DistributorInstruction::Claim {index, amount, proof} => {
+                msg!("Instruction: Claim");
+                let claimant = next_account_info(accounts_iter)?;
+                let distributor_ai = next_account_info(accounts_iter)?;
+                let claims_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let claimant_token = next_account_info(accounts_iter)?;
+                let mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    claimant.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Claimant must sign."
+                )?;
+                assert_with_msg(
+                    distributor_ai.owner == program_id,
+                    ProgramError::IncorrectProgramId,
+                    "Distributor must be owned by the distributor program"
+                )?;
+                let mut distributor = Distributor::try_from_slice(&distributor_ai.data.borrow())?;
+                assert_with_msg(
+                    distributor.vault == *vault_ai.key
+                        && distributor.mint == *mint.key
+                        && distributor.token_program == *token_program.key,
+                    DistributorError::DistributorMismatch.into(),
+                    "Accounts do not match the distributor"
+                )?;
+                assert_with_msg(
+                    index < distributor.num_recipients,
+                    DistributorError::IndexOutOfRange.into(),
+                    "Index out of range"
+                )?;
+                assert_with_msg(
+                    merkle::verify(&proof, &distributor.merkle_root, merkle::leaf_hash(index, claimant.key, amount)),
+                    DistributorError::InvalidProof.into(),
+                    "Invalid merkle proof"
+                )?;
+
+                let chunk_bytes = (index / CLAIM_CHUNK_BITS).to_le_bytes();
+                let (claims_key, claims_bump) = Pubkey::find_program_address(
+                    &[CLAIMS_SEED, distributor_ai.key.as_ref(), &chunk_bytes],
+                    program_id,
+                );
+                assert_with_msg(
+                    claims_key == *claims_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for claim bitmap"
+                )?;
+                // First claim of this chunk
+                if claims_ai.data_is_empty() {
+                    assert_with_msg(
+                        *system_program.key == SYSTEM_PROGRAM_ID,
+                        ProgramError::InvalidArgument,
+                        "Invalid passed in for system programs"
+                    )?;
+                    create_pda_account(
+                        claimant,
+                        claims_ai,
+                        CLAIM_CHUNK_BYTES,
+                        program_id,
+                        system_program,
+                        &[CLAIMS_SEED, distributor_ai.key.as_ref(), &chunk_bytes, &[claims_bump]],
+                    )?;
+                }
+
+                {
+                    let bit = index % CLAIM_CHUNK_BITS;
+                    let mut claims = claims_ai.data.borrow_mut();
+                    let byte = &mut claims[(bit / 8) as usize];
+                    let mask = 1u8 << (bit % 8);
+                    assert_with_msg(
+                        *byte & mask == 0,
+                        DistributorError::AlreadyClaimed.into(),
+                        "Index already claimed"
+                    )?;
+                    *byte |= mask;
+                }
+
+                distributor.total_claimed = distributor.total_claimed
+                    .checked_add(amount)
+                    .filter(|total_claimed| *total_claimed <= distributor.total_amount)
+                    .ok_or(DistributorError::ExceedsTotal)?;
+
+                // The token program checks that the destination holds the airdropped mint
+                assert_with_msg(
+                    TokenAccount::load(claimant_token)?.owner == *claimant.key,
+                    ProgramError::InvalidArgument,
+                    "Claimant token account must be owned by the claimant"
+                )?;
+                invoke_signed(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *vault_ai.key,
+                        *claimant_token.key,
+                        *distributor_ai.key,
+                        *mint.key,
+                        amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        vault_ai.clone(),
+                        claimant_token.clone(),
+                        distributor_ai.clone(),
+                        mint.clone(),
+                        token_config.clone(),
+                    ],
+                    &[&[
+                        DISTRIBUTOR_SEED,
+                        distributor.creator.as_ref(),
+                        &distributor.id.to_le_bytes(),
+                        &[distributor.bump],
+                    ]],
+                )?;
+
+                msg!("Claimed {} for index {}", amount, index);
+                distributor.serialize(&mut *distributor_ai.data.borrow_mut())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+pub const DISTRIBUTOR_SEED: &[u8] = b"distributor";
+pub const VAULT_SEED: &[u8] = b"vault";
+pub const CLAIMS_SEED: &[u8] = b"claims";
+
+// Number of indices tracked by one claim bitmap account. Bitmaps are split in chunks
+// because an account created through CPI cannot be larger than 10KiB
+pub const CLAIM_CHUNK_BYTES: usize = 8 * 1024;
+pub const CLAIM_CHUNK_BITS: u64 = CLAIM_CHUNK_BYTES as u64 * 8;
+
+/* A distributor is a PDA seeded by [DISTRIBUTOR_SEED, creator, id].
+ It owns the vault holding the airdropped tokens, seeded by [VAULT_SEED, distributor].
+ Claimed indices are tracked in bitmap chunks seeded by [CLAIMS_SEED, distributor, chunk],
+ chunk being index / CLAIM_CHUNK_BITS, created by the first claimant of the chunk.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Distributor {
+    pub creator: Pubkey,
+    pub id: u64, // lets a creator run several airdrops
+    pub token_program: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub total_claimed: u64,
+    pub num_recipients: u64,
+    pub bump: u8,
+}
+
+impl Distributor {
+    pub const LEN: usize = 32 + 8 + 32 * 3 + 32 + 8 * 3 + 1;
+}
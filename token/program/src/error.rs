@@ -5,6 +5,8 @@ use thiserror::Error;
 pub enum TokenError {
     #[error("Instruction not implemented.")]
     NotImplemented,
+    #[error("Token program is paused.")]
+    Paused,
 }
 
 impl From<TokenError> for ProgramError {
@@ -1,10 +1,257 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::state::Config;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub enum TokenInstruction {
-    InitializeMint,
+    /* Every instruction that mutates state, except Burn, takes the config PDA
+     right after its own accounts and fails while the program is paused.
+     Burn stays available so that holders can always exit.
+    */
+    // permanent_delegate can transfer or burn from any token account of the mint
+    // close_authority can close the mint with CloseMint once its supply is zero
+    // rate_authority can change the interest rate (basis points per year) with UpdateRate
+    // Accounts: [mint, mint authority, config]
+    InitializeMint {
+        permanent_delegate: Option<Pubkey>,
+        close_authority: Option<Pubkey>,
+        rate_authority: Option<Pubkey>,
+        rate: i16,
+    },
+    // Accounts: [token account, mint, owner, config]
     InitializeTokenAccount,
+    // Accounts: [token account, mint, mint authority, config]
     Mint {amount: u64},
+    // Accounts: [token account, mint, owner or permanent delegate]
     Burn {amount: u64},
+    // Accounts: [src token account, dst token account, owner, delegate or permanent delegate, mint, config]
     Transfer {amount: u64},
-}
\ No newline at end of file
+    // Accounts: [mint, destination for the lamports, close authority, config]
+    CloseMint,
+    // Accounts: [mint, rate authority, config]
+    UpdateRate {rate: i16},
+    // Accounts: [mint]. The converted value is returned with set_return_data as a borsh u64
+    AmountToUiAmount {amount: u64},
+    UiAmountToAmount {ui_amount: u64},
+    // Accounts: [src token account, dst token account, owner or permanent delegate, mint,
+    //  dst token account owner, receiving program, callback authority, config,
+    //  ...accounts forwarded to the receiving program]
+    // The dst token account owner must be an account owned by the receiving program.
+    // After the transfer the receiving program is invoked with an OnTokensReceived payload,
+    // see OnTokensReceived for the accounts it gets
+    TransferAndCall {amount: u64, data: Vec<u8>},
+    // Accounts: [config, payer, program data account, upgrade authority, system program]
+    // Only the upgrade authority of the program can create the config
+    InitializeConfig {pause_authority: Pubkey},
+    // Accounts: [config, pause authority]
+    Pause,
+    Unpause,
+    // Hands the mint over, e.g. to a governance PDA
+    // Accounts: [mint, current mint authority, config]
+    SetMintAuthority {new_authority: Pubkey},
+    // Lets delegate transfer up to amount out of the token account, replacing any previous approval
+    // Accounts: [token account, delegate, owner, config]
+    Approve {amount: u64},
+    // Accounts: [token account, owner, config]
+    Revoke,
+}
+
+// Prefix of the instruction data TransferAndCall sends to the receiving program,
+// so that it can tell the notification apart from its own instructions
+pub const ON_TOKENS_RECEIVED_TAG: [u8; 8] = *b"tkn_recv";
+
+// Seed of the PDA that the token program signs every callback with
+pub const CALLBACK_AUTHORITY_SEED: &[u8] = b"callback";
+
+pub fn find_callback_authority(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CALLBACK_AUTHORITY_SEED], program_id)
+}
+
+/* Anyone can invoke the receiving program with a forged tkn_recv payload, so the payload
+ alone proves nothing. The callback is invoked with the accounts
+   [callback authority (signer), dst token account, mint, dst token account owner, ...forwarded accounts]
+ and only the token program can sign for the callback authority, so a receiver must check
+ that the first account signed and that its key is find_callback_authority(token program id)
+ before trusting the payload. None of the accounts, not even the forwarded ones, keep the
+ signer privilege they had in the outer instruction.
+*/
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct OnTokensReceived {
+    pub sender: Pubkey, // signer of the transfer
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub data: Vec<u8>, // opaque data passed through from TransferAndCall
+}
+
+impl OnTokensReceived {
+    pub fn pack(&self) -> Result<Vec<u8>, ProgramError> {
+        let mut buf = ON_TOKENS_RECEIVED_TAG.to_vec();
+        buf.extend(self.try_to_vec()?);
+        Ok(buf)
+    }
+
+    // Returns None if data is not an OnTokensReceived notification
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        let payload = data.strip_prefix(&ON_TOKENS_RECEIVED_TAG[..])?;
+        Self::try_from_slice(payload).ok()
+    }
+}
+
+// Instruction builders, mostly useful for programs that CPI into the token program
+
+// Plain mint without permanent delegate, close authority or interest
+pub fn initialize_mint(
+    program_id: Pubkey,
+    mint: Pubkey,
+    mint_authority: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(mint, false),
+            AccountMeta::new_readonly(mint_authority, true),
+            AccountMeta::new_readonly(Config::find_address(&program_id).0, false),
+        ],
+        data: TokenInstruction::InitializeMint {
+            permanent_delegate: None,
+            close_authority: None,
+            rate_authority: None,
+            rate: 0,
+        }.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn initialize_token_account(
+    program_id: Pubkey,
+    token_account: Pubkey,
+    mint: Pubkey,
+    owner: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(token_account, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(owner, false),
+            AccountMeta::new_readonly(Config::find_address(&program_id).0, false),
+        ],
+        data: TokenInstruction::InitializeTokenAccount.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn transfer(
+    program_id: Pubkey,
+    src_token_account: Pubkey,
+    dst_token_account: Pubkey,
+    owner: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(src_token_account, false),
+            AccountMeta::new(dst_token_account, false),
+            AccountMeta::new_readonly(owner, true),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(Config::find_address(&program_id).0, false),
+        ],
+        data: TokenInstruction::Transfer {amount}.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn mint_to(
+    program_id: Pubkey,
+    token_account: Pubkey,
+    mint: Pubkey,
+    mint_authority: Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(token_account, false),
+            AccountMeta::new(mint, false),
+            AccountMeta::new_readonly(mint_authority, true),
+            AccountMeta::new_readonly(Config::find_address(&program_id).0, false),
+        ],
+        data: TokenInstruction::Mint {amount}.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn burn(
+    program_id: Pubkey,
+    token_account: Pubkey,
+    mint: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(token_account, false),
+            AccountMeta::new(mint, false),
+            AccountMeta::new_readonly(owner, true),
+        ],
+        data: TokenInstruction::Burn {amount}.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn set_mint_authority(
+    program_id: Pubkey,
+    mint: Pubkey,
+    mint_authority: Pubkey,
+    new_authority: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(mint, false),
+            AccountMeta::new_readonly(mint_authority, true),
+            AccountMeta::new_readonly(Config::find_address(&program_id).0, false),
+        ],
+        data: TokenInstruction::SetMintAuthority {new_authority}.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn approve(
+    program_id: Pubkey,
+    token_account: Pubkey,
+    delegate: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(token_account, false),
+            AccountMeta::new_readonly(delegate, false),
+            AccountMeta::new_readonly(owner, true),
+            AccountMeta::new_readonly(Config::find_address(&program_id).0, false),
+        ],
+        data: TokenInstruction::Approve {amount}.try_to_vec()?,
+        program_id,
+    })
+}
+
+pub fn revoke(
+    program_id: Pubkey,
+    token_account: Pubkey,
+    owner: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        accounts: vec![
+            AccountMeta::new(token_account, false),
+            AccountMeta::new_readonly(owner, true),
+            AccountMeta::new_readonly(Config::find_address(&program_id).0, false),
+        ],
+        data: TokenInstruction::Revoke.try_to_vec()?,
+        program_id,
+    })
+}
@@ -3,3 +3,7 @@ pub mod error;
 pub mod instruction;
 pub mod processor;
 pub mod state;
+pub mod utils;
+
+// Programs calling into the token program pin it to this id, so it must be deployed with the matching keypair
+solana_program::declare_id!("3S9ivK5tqr7ppGzBzuvtQLmcMYU4ofW1APK97JBFXqjc");
@@ -1,14 +1,21 @@
-use borsh::{BorshDeserialize};
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{AccountInfo, next_account_info},
     entrypoint::ProgramResult,
     msg,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    instruction::{AccountMeta, Instruction},
+    program::{invoke_signed, set_return_data},
     program_error::ProgramError,
-    pubkey::Pubkey
+    pubkey::Pubkey,
+    system_instruction,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 
-use crate::instruction::TokenInstruction;
-use crate::state::{AccountTag, Mint, TokenAccount};
+use crate::error::TokenError;
+use crate::instruction::{find_callback_authority, OnTokensReceived, TokenInstruction, CALLBACK_AUTHORITY_SEED};
+use crate::state::{AccountTag, Config, Mint, TokenAccount, CONFIG_SEED};
 
 pub struct Processor {}
 
@@ -21,9 +28,42 @@ pub fn asssert_with_msg(statement: bool, err: ProgramError, msg: &str) -> Progra
     }
 }
 
+// Loads the config and checks its address with the stored bump
+fn load_config(program_id: &Pubkey, config_ai: &AccountInfo) -> Result<Config, ProgramError> {
+    asssert_with_msg(
+        config_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Config must be owned by the token program"
+    )?;
+    let config = Config::load(config_ai)?;
+    asssert_with_msg(
+        config.address(program_id)? == *config_ai.key,
+        ProgramError::InvalidArgument,
+        "Invalid PDA seeds for config"
+    )?;
+    Ok(config)
+}
+
+// Fails if the program is paused. The config PDA may not exist yet, in which case nothing is paused.
+// Only that case needs the bump search, as the bump is not known before the config is created
+pub fn assert_not_paused(program_id: &Pubkey, config_ai: &AccountInfo) -> ProgramResult {
+    if config_ai.data_is_empty() {
+        return asssert_with_msg(
+            Config::find_address(program_id).0 == *config_ai.key,
+            ProgramError::InvalidArgument,
+            "Invalid PDA seeds for config"
+        )
+    }
+    if load_config(program_id, config_ai)?.paused {
+        msg!("Token program is paused");
+        return Err(TokenError::Paused.into())
+    }
+    Ok(())
+}
+
 impl Processor {
     pub fn process_instruction(
-        _program_id: &Pubkey,
+        program_id: &Pubkey,
         accounts: &[AccountInfo],
         instruction_data: &[u8],
     ) -> ProgramResult {
@@ -34,10 +74,17 @@ impl Processor {
         let accounts_iter = &mut accounts.iter();
 
         match instruction {
-            TokenInstruction::InitializeMint => {
+            TokenInstruction::InitializeMint {
+                permanent_delegate,
+                close_authority,
+                rate_authority,
+                rate,
+            } => {
 
                 let mint_ai = next_account_info(accounts_iter)?; // AccountInfo object
                 let mint_authority = next_account_info(accounts_iter)?; // AccountInfo object
+                let config_ai = next_account_info(accounts_iter)?;
+                assert_not_paused(program_id, config_ai)?;
                 let mut mint = Mint::load_unchecked(mint_ai)?; // Mint object
                 
                 asssert_with_msg(
@@ -45,10 +92,22 @@ impl Processor {
                     ProgramError::MissingRequiredSignature,
                     "Mint authority must sign!"
                 )?;
+                // The permanent delegate is fixed at initialization, so a mint cannot be initialized twice
+                asssert_with_msg(
+                    mint.tag == AccountTag::Uninitialized,
+                    ProgramError::AccountAlreadyInitialized,
+                    "Mint is already initialized."
+                )?;
 
                 mint.tag = AccountTag::Mint;
                 mint.authority = *mint_authority.key;
                 mint.supply = 0;
+                mint.permanent_delegate = permanent_delegate;
+                mint.close_authority = close_authority;
+                mint.rate_authority = rate_authority;
+                mint.rate = rate;
+                mint.last_update_timestamp = Clock::get()?.unix_timestamp;
+                mint.accrued_multiplier = 1.0;
                 mint.save(mint_ai) // serializes data and returns it
             }
             TokenInstruction::InitializeTokenAccount => {
@@ -56,12 +115,23 @@ impl Processor {
                 let mint_ai = next_account_info(accounts_iter)?;  // AccountInfo object
                 //let mint = Mint::load(mint_ai)?; // validated Mint object
                 let owner = next_account_info(accounts_iter)?;  // AccountInfo object
+                let config_ai = next_account_info(accounts_iter)?;
+                assert_not_paused(program_id, config_ai)?;
                 let mut token_account = TokenAccount::load_unchecked(token_account_ai)?; // TokenAccount object
 
+                // Otherwise anyone could reset the owner and balance of an existing token account
+                asssert_with_msg(
+                    token_account.tag == AccountTag::Uninitialized,
+                    ProgramError::AccountAlreadyInitialized,
+                    "Token account is already initialized."
+                )?;
+
                 token_account.tag = AccountTag::TokenAccount;
                 token_account.owner = *owner.key;
                 token_account.mint = *mint_ai.key;
                 token_account.amount = 0;
+                token_account.delegate = None;
+                token_account.delegated_amount = 0;
                 token_account.save(token_account_ai)
             }
             TokenInstruction::Mint {amount} => {
@@ -69,6 +139,8 @@ impl Processor {
                 let token_account_ai = next_account_info(accounts_iter)?;
                 let mint_ai = next_account_info(accounts_iter)?;
                 let mint_authority = next_account_info(accounts_iter)?;
+                let config_ai = next_account_info(accounts_iter)?;
+                assert_not_paused(program_id, config_ai)?;
 
                 let mut token_account = TokenAccount::load(token_account_ai)?;
                 let mut mint = Mint::load(mint_ai)?;
@@ -78,10 +150,21 @@ impl Processor {
                     ProgramError::MissingRequiredSignature,
                     "Mint authority must sign."
                 )?;
+                asssert_with_msg(
+                    mint.authority == *mint_authority.key,
+                    ProgramError::MissingRequiredSignature,
+                    "Mint authority mismatch."
+                )?;
+                asssert_with_msg(
+                    token_account.mint == *mint_ai.key,
+                    ProgramError::InvalidAccountData,
+                    "Token account mint mismatch."
+                )?;
 
-                // unsafe --> check for numerical overflow
-                mint.supply += amount;
-                token_account.amount += amount;
+                mint.supply = mint.supply
+                    .checked_add(amount)
+                    .ok_or(ProgramError::InvalidArgument)?;
+                token_account.amount += amount; // cannot overflow as amount <= supply
 
                 token_account.save(token_account_ai)?;
                 mint.save(mint_ai) //todo check this out
@@ -100,9 +183,16 @@ impl Processor {
                     ProgramError::MissingRequiredSignature,
                     "Token owner must sign."
                 )?;
-                // Assert that owner is the token account owner
                 asssert_with_msg(
-                    token_account.owner == *owner.key,
+                    token_account.mint == *mint_ai.key,
+                    ProgramError::InvalidAccountData,
+                    "Token account mint mismatch."
+                )?;
+                // Assert that owner is the token account owner or the permanent delegate of the mint
+                let is_permanent_delegate = token_account.owner != *owner.key
+                    && mint.is_permanent_delegate(owner.key);
+                asssert_with_msg(
+                    token_account.owner == *owner.key || is_permanent_delegate,
                     ProgramError::MissingRequiredSignature,
                     "Token owner mistmatch."
                 )?;
@@ -113,6 +203,15 @@ impl Processor {
                     "Cannot burn amount superior than the amount in the token_account."
                 )?;
 
+                if is_permanent_delegate {
+                    msg!(
+                        "Permanent delegate {} burned {} tokens from {}",
+                        owner.key,
+                        amount,
+                        token_account_ai.key
+                    );
+                }
+
                 mint.supply -= amount;
                 token_account.amount -= amount;
                 token_account.save(token_account_ai)?;
@@ -123,35 +222,444 @@ impl Processor {
                 let src_token_account_ai = next_account_info(accounts_iter)?;
                 let dst_token_account_ai = next_account_info(accounts_iter)?;
                 let owner = next_account_info(accounts_iter)?;
+                let mint_ai = next_account_info(accounts_iter)?;
+                let config_ai = next_account_info(accounts_iter)?;
+                assert_not_paused(program_id, config_ai)?;
+
+                Self::process_transfer(
+                    src_token_account_ai,
+                    dst_token_account_ai,
+                    owner,
+                    mint_ai,
+                    amount,
+                )
+            }
+            TokenInstruction::CloseMint => {
+                msg!("Instruction CloseMint");
+                let mint_ai = next_account_info(accounts_iter)?;
+                let destination_ai = next_account_info(accounts_iter)?;
+                let close_authority = next_account_info(accounts_iter)?;
+                let config_ai = next_account_info(accounts_iter)?;
+                assert_not_paused(program_id, config_ai)?;
+
+                let mint = Mint::load(mint_ai)?;
+
+                asssert_with_msg(
+                    close_authority.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Close authority must sign."
+                )?;
+                asssert_with_msg(
+                    mint.close_authority == Some(*close_authority.key),
+                    ProgramError::MissingRequiredSignature,
+                    "Close authority mismatch."
+                )?;
+                asssert_with_msg(
+                    mint.supply == 0,
+                    ProgramError::InvalidAccountData,
+                    "Cannot close a mint with a non-zero supply."
+                )?;
+                asssert_with_msg(
+                    mint_ai.key != destination_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Destination cannot be the mint itself."
+                )?;
+
+                // Move all the rent to the destination, the runtime garbage collects the empty account
+                let lamports = mint_ai.lamports();
+                **destination_ai.lamports.borrow_mut() = destination_ai
+                    .lamports()
+                    .checked_add(lamports)
+                    .ok_or(ProgramError::InvalidAccountData)?;
+                **mint_ai.lamports.borrow_mut() = 0;
+
+                // Zeroing the data sets the tag back to AccountTag::Uninitialized
+                mint_ai.data.borrow_mut().fill(0);
+                Ok(())
+            }
+            TokenInstruction::UpdateRate {rate} => {
+                msg!("Instruction UpdateRate");
+                let mint_ai = next_account_info(accounts_iter)?;
+                let rate_authority = next_account_info(accounts_iter)?;
+                let config_ai = next_account_info(accounts_iter)?;
+                assert_not_paused(program_id, config_ai)?;
+
+                let mut mint = Mint::load(mint_ai)?;
+
+                asssert_with_msg(
+                    rate_authority.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Rate authority must sign."
+                )?;
+                asssert_with_msg(
+                    mint.rate_authority == Some(*rate_authority.key),
+                    ProgramError::MissingRequiredSignature,
+                    "Rate authority mismatch."
+                )?;
+
+                // Interest accrued at the old rate is kept in the multiplier
+                mint.accrue(Clock::get()?.unix_timestamp);
+                mint.rate = rate;
+                mint.save(mint_ai)
+            }
+            TokenInstruction::AmountToUiAmount {amount} => {
+                msg!("Instruction AmountToUiAmount");
+                let mint_ai = next_account_info(accounts_iter)?;
+                let mint = Mint::load(mint_ai)?;
+
+                let ui_amount = mint
+                    .amount_to_ui_amount(amount, Clock::get()?.unix_timestamp)
+                    .ok_or(ProgramError::InvalidArgument)?;
+                msg!("UI amount {}", ui_amount);
+                set_return_data(&ui_amount.try_to_vec()?);
+                Ok(())
+            }
+            TokenInstruction::UiAmountToAmount {ui_amount} => {
+                msg!("Instruction UiAmountToAmount");
+                let mint_ai = next_account_info(accounts_iter)?;
+                let mint = Mint::load(mint_ai)?;
+
+                let amount = mint
+                    .ui_amount_to_amount(ui_amount, Clock::get()?.unix_timestamp)
+                    .ok_or(ProgramError::InvalidArgument)?;
+                msg!("Amount {}", amount);
+                set_return_data(&amount.try_to_vec()?);
+                Ok(())
+            }
+            TokenInstruction::TransferAndCall {amount, data} => {
+                msg!("Instruction TransferAndCall");
+                let src_token_account_ai = next_account_info(accounts_iter)?;
+                let dst_token_account_ai = next_account_info(accounts_iter)?;
+                let owner = next_account_info(accounts_iter)?;
+                let mint_ai = next_account_info(accounts_iter)?;
+                let dst_owner_ai = next_account_info(accounts_iter)?;
+                let receiver_program = next_account_info(accounts_iter)?;
+                let callback_authority = next_account_info(accounts_iter)?;
+                let config_ai = next_account_info(accounts_iter)?;
+                assert_not_paused(program_id, config_ai)?;
+                let forwarded_accounts = accounts_iter.as_slice();
 
-                let mut src_token_account = TokenAccount::load(src_token_account_ai)?;
-                let mut dst_token_account = TokenAccount::load(dst_token_account_ai)?;
+                // State is saved before the CPI so that the receiver sees the new balance
+                Self::process_transfer(
+                    src_token_account_ai,
+                    dst_token_account_ai,
+                    owner,
+                    mint_ai,
+                    amount,
+                )?;
+
+                let dst_token_account = TokenAccount::load(dst_token_account_ai)?;
+                asssert_with_msg(
+                    dst_token_account.owner == *dst_owner_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Destination owner mismatch."
+                )?;
+                asssert_with_msg(
+                    *dst_owner_ai.owner == *receiver_program.key && receiver_program.executable,
+                    ProgramError::IncorrectProgramId,
+                    "Receiving program must own the destination owner account."
+                )?;
+                let (callback_authority_key, callback_bump) = find_callback_authority(program_id);
+                asssert_with_msg(
+                    callback_authority_key == *callback_authority.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for callback authority"
+                )?;
+
+                let payload = OnTokensReceived {
+                    sender: *owner.key,
+                    source: *src_token_account_ai.key,
+                    destination: *dst_token_account_ai.key,
+                    mint: *mint_ai.key,
+                    amount,
+                    data,
+                };
+
+                // No signature of the outer instruction is forwarded, the receiver only learns
+                // the sender's key. The callback authority signature proves the call comes from here
+                let mut metas = vec![
+                    AccountMeta::new_readonly(*callback_authority.key, true),
+                    AccountMeta::new_readonly(*dst_token_account_ai.key, false),
+                    AccountMeta::new_readonly(*mint_ai.key, false),
+                    AccountMeta {
+                        pubkey: *dst_owner_ai.key,
+                        is_signer: false,
+                        is_writable: dst_owner_ai.is_writable,
+                    },
+                ];
+                let mut account_infos = vec![
+                    callback_authority.clone(),
+                    dst_token_account_ai.clone(),
+                    mint_ai.clone(),
+                    dst_owner_ai.clone(),
+                ];
+                for ai in forwarded_accounts {
+                    metas.push(AccountMeta {
+                        pubkey: *ai.key,
+                        is_signer: false,
+                        is_writable: ai.is_writable,
+                    });
+                    account_infos.push(ai.clone());
+                }
+                account_infos.push(receiver_program.clone());
+
+                // An error in the callback fails the whole instruction, reverting the transfer
+                invoke_signed(
+                    &Instruction {
+                        program_id: *receiver_program.key,
+                        accounts: metas,
+                        data: payload.pack()?,
+                    },
+                    &account_infos,
+                    &[&[CALLBACK_AUTHORITY_SEED, &[callback_bump]]],
+                )
+            }
+            TokenInstruction::InitializeConfig {pause_authority} => {
+                msg!("Instruction InitializeConfig");
+                let config_ai = next_account_info(accounts_iter)?;
+                let payer = next_account_info(accounts_iter)?;
+                let program_data_ai = next_account_info(accounts_iter)?;
+                let upgrade_authority = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+
+                let (config_key, config_bump) = Config::find_address(program_id);
+                asssert_with_msg(
+                    config_key == *config_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for config"
+                )?;
+                asssert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+
+                // Whoever creates the config picks the pause authority, so restrict it
+                // to the upgrade authority stored in the program data account
+                let (program_data_key, _) = Pubkey::find_program_address(
+                    &[program_id.as_ref()],
+                    &bpf_loader_upgradeable::id(),
+                );
+                asssert_with_msg(
+                    program_data_key == *program_data_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid program data account"
+                )?;
+                let program_data: UpgradeableLoaderState = program_data_ai
+                    .data
+                    .borrow()
+                    .get(..UpgradeableLoaderState::size_of_programdata_metadata())
+                    .and_then(|data| bincode::deserialize(data).ok())
+                    .ok_or(ProgramError::InvalidAccountData)?;
+                let upgrade_authority_address = match program_data {
+                    UpgradeableLoaderState::ProgramData {upgrade_authority_address, ..} => upgrade_authority_address,
+                    _ => return Err(ProgramError::InvalidAccountData),
+                };
+                asssert_with_msg(
+                    upgrade_authority.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Upgrade authority must sign."
+                )?;
+                asssert_with_msg(
+                    upgrade_authority_address == Some(*upgrade_authority.key),
+                    ProgramError::MissingRequiredSignature,
+                    "Upgrade authority mismatch."
+                )?;
+
+                invoke_signed(
+                    &system_instruction::create_account(
+                        payer.key,
+                        config_ai.key,
+                        Rent::get()?.minimum_balance(Config::LEN),
+                        Config::LEN as u64,
+                        program_id,
+                    ),
+                    &[payer.clone(), config_ai.clone(), system_program.clone()],
+                    &[&[CONFIG_SEED, &[config_bump]]],
+                )?;
+
+                let config = Config {
+                    tag: AccountTag::Config,
+                    pause_authority,
+                    paused: false,
+                    bump: config_bump,
+                };
+                config.save(config_ai)
+            }
+            TokenInstruction::Pause | TokenInstruction::Unpause => {
+                let paused = matches!(instruction, TokenInstruction::Pause);
+                msg!("Instruction {}", if paused { "Pause" } else { "Unpause" });
+                let config_ai = next_account_info(accounts_iter)?;
+                let pause_authority = next_account_info(accounts_iter)?;
+
+                let mut config = load_config(program_id, config_ai)?;
+
+                asssert_with_msg(
+                    pause_authority.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Pause authority must sign."
+                )?;
+                asssert_with_msg(
+                    config.pause_authority == *pause_authority.key,
+                    ProgramError::MissingRequiredSignature,
+                    "Pause authority mismatch."
+                )?;
+
+                config.paused = paused;
+                config.save(config_ai)
+            }
+            TokenInstruction::SetMintAuthority {new_authority} => {
+                msg!("Instruction SetMintAuthority");
+                let mint_ai = next_account_info(accounts_iter)?;
+                let mint_authority = next_account_info(accounts_iter)?;
+                let config_ai = next_account_info(accounts_iter)?;
+                assert_not_paused(program_id, config_ai)?;
+
+                let mut mint = Mint::load(mint_ai)?;
+
+                asssert_with_msg(
+                    mint_authority.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Mint authority must sign."
+                )?;
+                asssert_with_msg(
+                    mint.authority == *mint_authority.key,
+                    ProgramError::MissingRequiredSignature,
+                    "Mint authority mismatch."
+                )?;
+
+                mint.authority = new_authority;
+                mint.save(mint_ai)
+            }
+            TokenInstruction::Approve {amount} => {
+                msg!("Instruction Approve");
+                let token_account_ai = next_account_info(accounts_iter)?;
+                let delegate = next_account_info(accounts_iter)?;
+                let owner = next_account_info(accounts_iter)?;
+                let config_ai = next_account_info(accounts_iter)?;
+                assert_not_paused(program_id, config_ai)?;
+
+                let mut token_account = TokenAccount::load(token_account_ai)?;
 
                 asssert_with_msg(
                     owner.is_signer,
                     ProgramError::MissingRequiredSignature,
-                    "Token owner must sign"
+                    "Token owner must sign."
                 )?;
-                // Assert that owner is the token account owner
                 asssert_with_msg(
-                    src_token_account.owner == *owner.key,
+                    token_account.owner == *owner.key,
                     ProgramError::MissingRequiredSignature,
                     "Token owner mistmatch."
                 )?;
-                // Assert that owner is the token account owner
+
+                token_account.delegate = Some(*delegate.key);
+                token_account.delegated_amount = amount;
+                token_account.save(token_account_ai)
+            }
+            TokenInstruction::Revoke => {
+                msg!("Instruction Revoke");
+                let token_account_ai = next_account_info(accounts_iter)?;
+                let owner = next_account_info(accounts_iter)?;
+                let config_ai = next_account_info(accounts_iter)?;
+                assert_not_paused(program_id, config_ai)?;
+
+                let mut token_account = TokenAccount::load(token_account_ai)?;
+
                 asssert_with_msg(
-                    src_token_account.mint == dst_token_account.mint,
-                    ProgramError::InvalidAccountData,
-                    "Token account mints do not match."
+                    owner.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Token owner must sign."
+                )?;
+                asssert_with_msg(
+                    token_account.owner == *owner.key,
+                    ProgramError::MissingRequiredSignature,
+                    "Token owner mistmatch."
                 )?;
 
-                src_token_account.amount -= amount;
-                dst_token_account.amount += amount;
-                
-                src_token_account.save(src_token_account_ai)?;
-                dst_token_account.save(src_token_account_ai) // todo check this out
+                token_account.delegate = None;
+                token_account.delegated_amount = 0;
+                token_account.save(token_account_ai)
             }
         }
     }
- }
 
+    // Moves amount tokens between two token accounts of the same mint.
+    // owner must be the source account owner, its approved delegate or the permanent delegate of the mint
+    fn process_transfer<'a>(
+        src_token_account_ai: &AccountInfo<'a>,
+        dst_token_account_ai: &AccountInfo<'a>,
+        owner: &AccountInfo<'a>,
+        mint_ai: &AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
+        let mut src_token_account = TokenAccount::load(src_token_account_ai)?;
+        let dst_token_account = TokenAccount::load(dst_token_account_ai)?;
+        let mint = Mint::load(mint_ai)?;
+
+        asssert_with_msg(
+            owner.is_signer,
+            ProgramError::MissingRequiredSignature,
+            "Token owner must sign"
+        )?;
+        asssert_with_msg(
+            src_token_account.mint == *mint_ai.key,
+            ProgramError::InvalidAccountData,
+            "Token account mint mismatch."
+        )?;
+        // Assert that owner is the token account owner or the permanent delegate of the mint
+        let is_permanent_delegate = src_token_account.owner != *owner.key
+            && mint.is_permanent_delegate(owner.key);
+        // The approved delegate is only used when the signer has no other right on the account
+        let is_delegate = src_token_account.owner != *owner.key
+            && !is_permanent_delegate
+            && src_token_account.delegate == Some(*owner.key);
+        asssert_with_msg(
+            src_token_account.owner == *owner.key || is_permanent_delegate || is_delegate,
+            ProgramError::MissingRequiredSignature,
+            "Token owner mistmatch."
+        )?;
+        if is_delegate {
+            asssert_with_msg(
+                src_token_account.delegated_amount >= amount,
+                ProgramError::InsufficientFunds,
+                "Cannot transfer amount superior than the delegated amount."
+            )?;
+            src_token_account.delegated_amount -= amount;
+            // The approval is used up
+            if src_token_account.delegated_amount == 0 {
+                src_token_account.delegate = None;
+            }
+        }
+        // Assert that both token accounts hold the same token
+        asssert_with_msg(
+            src_token_account.mint == dst_token_account.mint,
+            ProgramError::InvalidAccountData,
+            "Token account mints do not match."
+        )?;
+        asssert_with_msg(
+            src_token_account.amount >= amount,
+            ProgramError::InsufficientFunds,
+            "Cannot transfer amount superior than the amount in the token_account."
+        )?;
+
+        if is_permanent_delegate {
+            msg!(
+                "Permanent delegate {} transferred {} tokens from {} to {}",
+                owner.key,
+                amount,
+                src_token_account_ai.key,
+                dst_token_account_ai.key
+            );
+        }
+
+        src_token_account.amount -= amount;
+        src_token_account.save(src_token_account_ai)?;
+
+        // Reload the destination in case it is the same account as the source
+        let mut dst_token_account = TokenAccount::load(dst_token_account_ai)?;
+        dst_token_account.amount = dst_token_account.amount
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        dst_token_account.save(dst_token_account_ai)
+    }
+ }
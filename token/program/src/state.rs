@@ -20,9 +20,13 @@ AccountTag allows us to distinguish between Mint account and TokenAccounts
 pub enum AccountTag {
     Uninitialized,
     Mint,
-    TokenAccount
+    TokenAccount,
+    Config,
 }
 
+pub const BASIS_POINTS: f64 = 10_000.0;
+pub const SECONDS_PER_YEAR: f64 = 60.0 * 60.0 * 24.0 * 365.24;
+
 // We need 2 structs for our token program: Mint and TokenAccount
 
 #[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
@@ -30,14 +34,30 @@ pub struct Mint {
     pub tag: AccountTag,
     pub authority: Pubkey,
     pub supply: u64,
+    // Optional key that can transfer or burn tokens out of any TokenAccount of this mint.
+    // Set once in InitializeMint and never changed afterwards
+    pub permanent_delegate: Option<Pubkey>,
+    // Optional key allowed to close the mint and reclaim its rent once the supply is back to zero
+    pub close_authority: Option<Pubkey>,
+    // Interest bearing configuration. The raw TokenAccount::amount never changes,
+    // only the UI amount derived from it grows with the accrued multiplier
+    pub rate_authority: Option<Pubkey>,
+    pub rate: i16, // basis points per year
+    pub last_update_timestamp: i64,
+    pub accrued_multiplier: f64, // multiplier accrued up to last_update_timestamp
 }
 
 impl Mint {
+    // Size of the account to allocate for a mint, assuming every optional field is set
+    pub const LEN: usize = 1 + 32 + 8 + (1 + 32) + (1 + 32) + (1 + 32) + 2 + 8 + 8;
+
     // Helper functions
+    // The Option fields make the serialized size variable, so we deserialize
+    // from the front of the buffer and ignore any trailing bytes
     pub fn load_unchecked(ai: &AccountInfo) -> Result<Self, ProgramError> {
         return Ok(
-            Self::try_from_slice(
-                &ai.data.borrow()
+            Self::deserialize(
+                &mut &ai.data.borrow()[..]
             )?
         )
     }
@@ -50,7 +70,7 @@ impl Mint {
     }
 
     pub fn load(ai: &AccountInfo) -> Result<Self, ProgramError> {
-        let mint = Self::try_from_slice(&ai.data.borrow())?;
+        let mint = Self::load_unchecked(ai)?;
         mint.validate()?;
         return Ok(mint)
     }
@@ -62,6 +82,41 @@ impl Mint {
             )?
         )
     }
+
+    pub fn is_permanent_delegate(&self, key: &Pubkey) -> bool {
+        self.permanent_delegate == Some(*key)
+    }
+
+    // Multiplier at unix_timestamp, compounding the current rate continuously since the last update
+    pub fn multiplier_at(&self, unix_timestamp: i64) -> f64 {
+        let elapsed = unix_timestamp.saturating_sub(self.last_update_timestamp).max(0) as f64;
+        let rate = self.rate as f64 / BASIS_POINTS;
+        self.accrued_multiplier * (rate * elapsed / SECONDS_PER_YEAR).exp()
+    }
+
+    // Folds the interest accrued so far into accrued_multiplier, must be called before changing the rate
+    pub fn accrue(&mut self, unix_timestamp: i64) {
+        self.accrued_multiplier = self.multiplier_at(unix_timestamp);
+        self.last_update_timestamp = unix_timestamp;
+    }
+
+    pub fn amount_to_ui_amount(&self, amount: u64, unix_timestamp: i64) -> Option<u64> {
+        let ui_amount = (amount as f64 * self.multiplier_at(unix_timestamp)).floor();
+        if ui_amount.is_finite() && ui_amount >= 0.0 && ui_amount <= u64::MAX as f64 {
+            Some(ui_amount as u64)
+        } else {
+            None
+        }
+    }
+
+    pub fn ui_amount_to_amount(&self, ui_amount: u64, unix_timestamp: i64) -> Option<u64> {
+        let amount = (ui_amount as f64 / self.multiplier_at(unix_timestamp)).floor();
+        if amount.is_finite() && amount >= 0.0 && amount <= u64::MAX as f64 {
+            Some(amount as u64)
+        } else {
+            None
+        }
+    }
 }
 
 // Token Account
@@ -72,14 +127,21 @@ pub struct TokenAccount {
     pub owner: Pubkey,
     pub mint: Pubkey, // specifies which Token Type / Mint (e.g. USDC, BTC, etc.)
     pub amount: u64,
+    // Optional key allowed to transfer up to delegated_amount out of this account, set with Approve
+    pub delegate: Option<Pubkey>,
+    pub delegated_amount: u64,
 }
 
 impl TokenAccount {
+        // Size of the account to allocate for a token account, assuming a delegate is set
+        pub const LEN: usize = 1 + 32 + 32 + 8 + (1 + 32) + 8;
+
         // Helper functions
+        // As for Mint, the delegate makes the serialized size variable
         pub fn load_unchecked(ai: &AccountInfo) -> Result<Self, ProgramError> {
             return Ok(
-                Self::try_from_slice(
-                    &ai.data.borrow()
+                Self::deserialize(
+                    &mut &ai.data.borrow()[..]
                 )?
             )
         }
@@ -92,9 +154,9 @@ impl TokenAccount {
         }
     
         pub fn load(ai: &AccountInfo) -> Result<Self, ProgramError> {
-            let mint = Self::try_from_slice(&ai.data.borrow())?;
-            mint.validate()?;
-            return Ok(mint)
+            let token_account = Self::load_unchecked(ai)?;
+            token_account.validate()?;
+            return Ok(token_account)
         }
     
         pub fn save(&self, ai: &AccountInfo) -> ProgramResult {
@@ -104,4 +166,47 @@ impl TokenAccount {
                 )?
             )
         }
-}
\ No newline at end of file
+}
+
+// Global config
+
+// Seed of the single program wide config PDA
+pub const CONFIG_SEED: &[u8] = b"config";
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Config {
+    pub tag: AccountTag,
+    pub pause_authority: Pubkey,
+    pub paused: bool,
+    // Stored so that instructions can check the config address without searching for the bump
+    pub bump: u8,
+}
+
+impl Config {
+    pub const LEN: usize = 1 + 32 + 1 + 1;
+
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+    }
+
+    pub fn address(&self, program_id: &Pubkey) -> Result<Pubkey, ProgramError> {
+        Ok(Pubkey::create_program_address(&[CONFIG_SEED, &[self.bump]], program_id)?)
+    }
+
+    fn validate(&self) -> ProgramResult {
+        if self.tag != AccountTag::Config {
+            return Err(ProgramError::InvalidAccountData)
+        }
+        Ok(())
+    }
+
+    pub fn load(ai: &AccountInfo) -> Result<Self, ProgramError> {
+        let config = Self::try_from_slice(&ai.data.borrow())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn save(&self, ai: &AccountInfo) -> ProgramResult {
+        Ok(self.serialize(&mut *ai.data.borrow_mut())?)
+    }
+}
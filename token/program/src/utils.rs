@@ -0,0 +1,68 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::processor::asssert_with_msg;
+
+// Helpers for programs that CPI into the token program
+
+// Every CPI forwards signer privileges to the token program, so it must be the real one
+pub fn assert_token_program(token_program: &AccountInfo) -> ProgramResult {
+    asssert_with_msg(
+        crate::check_id(token_program.key),
+        ProgramError::IncorrectProgramId,
+        "Invalid token program"
+    )
+}
+
+/* Anyone can send lamports to a PDA before it is created, which makes create_account fail.
+   In that case the missing rent is topped up and the account is allocated and assigned
+   separately, which only needs the PDA signature.
+*/
+pub fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    system_program: &AccountInfo<'a>,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent_exempt_lamports = Rent::get()?.minimum_balance(space);
+    if new_account.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                new_account.key,
+                rent_exempt_lamports,
+                space as u64,
+                owner,
+            ),
+            &[payer.clone(), new_account.clone(), system_program.clone()],
+            &[signer_seeds],
+        )
+    }
+
+    let missing_lamports = rent_exempt_lamports.saturating_sub(new_account.lamports());
+    if missing_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, new_account.key, missing_lamports),
+            &[payer.clone(), new_account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(new_account.key, space as u64),
+        &[new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(new_account.key, owner),
+        &[new_account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )
+}
@@ -0,0 +1,20 @@
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum StakingError {
+    #[error("Instruction not implemented.")]
+    NotImplemented,
+    #[error("Account does not match the staking pool.")]
+    PoolMismatch,
+    #[error("Cannot unstake more than the staked amount.")]
+    InsufficientStake,
+    #[error("Numerical overflow.")]
+    MathOverflow,
+}
+
+impl From<StakingError> for ProgramError {
+    fn from(e: StakingError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
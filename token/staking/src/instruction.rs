@@ -0,0 +1,20 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum StakingInstruction {
+    // Creates the pool, its stake vault and its reward mint
+    // Accounts: [creator, pool, vault, reward mint, stake mint, token program,
+    //  token program config, system program]
+    InitializePool {reward_rate: u64},
+    // Creates the user stake account on first use
+    // Accounts: [user, pool, user stake, vault, user stake token account, stake mint,
+    //  token program, token program config, system program]
+    Stake {amount: u64},
+    // Accounts: [user, pool, user stake, vault, user stake token account, stake mint,
+    //  token program, token program config]
+    Unstake {amount: u64},
+    // Mints every reward owed to the user
+    // Accounts: [user, pool, user stake, reward mint, user reward token account,
+    //  token program, token program config]
+    ClaimRewards,
+}
@@ -0,0 +1,396 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{clock::Clock, Sysvar},
+};
+use token_program::state::{Mint, TokenAccount};
+use token_program::utils::{assert_token_program, create_pda_account};
+
+use crate::error::StakingError;
+use crate::instruction::StakingInstruction;
+use crate::state::{StakingPool, UserStake, POOL_SEED, REWARD_MINT_SEED, STAKE_SEED, VAULT_SEED};
+
+pub fn assert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
+    if !statement {
+        msg!(msg);
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+fn load_pool(program_id: &Pubkey, pool_ai: &AccountInfo) -> Result<StakingPool, ProgramError> {
+    assert_with_msg(
+        pool_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Pool must be owned by the staking program"
+    )?;
+    Ok(StakingPool::try_from_slice(&pool_ai.data.borrow())?)
+}
+
+fn load_user_stake(
+    program_id: &Pubkey,
+    user_stake_ai: &AccountInfo,
+    pool_ai: &AccountInfo,
+    user: &AccountInfo,
+) -> Result<UserStake, ProgramError> {
+    assert_with_msg(
+        user_stake_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "User stake must be owned by the staking program"
+    )?;
+    let user_stake = UserStake::try_from_slice(&user_stake_ai.data.borrow())?;
+    assert_with_msg(
+        user_stake.owner == *user.key && user_stake.pool == *pool_ai.key,
+        StakingError::PoolMismatch.into(),
+        "User stake does not match the user and pool"
+    )?;
+    Ok(user_stake)
+}
+
+/* Staked tokens sit in a vault owned by the pool PDA. Rewards are never held by the
+   program, they are minted on claim through a CPI signed by the pool PDA,
+   which is the authority of the reward mint.
+*/
+pub struct Processor {}
+
+impl Processor {
+    pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8]
+    ) -> ProgramResult {
+        let instruction = StakingInstruction::try_from_slice(instruction_data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        let accounts_iter = &mut accounts.iter();
+
+        match instruction {
+            StakingInstruction::InitializePool {reward_rate} => {
+                msg!("Instruction: InitializePool");
+                let creator = next_account_info(accounts_iter)?;
+                let pool_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let reward_mint = next_account_info(accounts_iter)?;
+                let stake_mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    creator.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Creator must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                assert_with_msg(
+                    stake_mint.owner == token_program.key,
+                    ProgramError::IncorrectProgramId,
+                    "Stake mint must belong to the token program"
+                )?;
+                Mint::load(stake_mint)?;
+
+                let (pool_key, bump) = Pubkey::find_program_address(
+                    &[POOL_SEED, stake_mint.key.as_ref(), creator.key.as_ref()],
+                    program_id,
+                );
+                let (vault_key, vault_bump) = Pubkey::find_program_address(
+                    &[VAULT_SEED, pool_key.as_ref()],
+                    program_id,
+                );
+                let (reward_mint_key, reward_mint_bump) = Pubkey::find_program_address(
+                    &[REWARD_MINT_SEED, pool_key.as_ref()],
+                    program_id,
+                );
+                assert_with_msg(
+                    pool_key == *pool_ai.key
+                        && vault_key == *vault_ai.key
+                        && reward_mint_key == *reward_mint.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for pool accounts"
+                )?;
+
+                let pool_seeds: &[&[u8]] = &[POOL_SEED, stake_mint.key.as_ref(), creator.key.as_ref(), &[bump]];
+                create_pda_account(creator, pool_ai, StakingPool::LEN, program_id, system_program, pool_seeds)?;
+                create_pda_account(
+                    creator,
+                    vault_ai,
+                    TokenAccount::LEN,
+                    token_program.key,
+                    system_program,
+                    &[VAULT_SEED, pool_key.as_ref(), &[vault_bump]],
+                )?;
+                create_pda_account(
+                    creator,
+                    reward_mint,
+                    Mint::LEN,
+                    token_program.key,
+                    system_program,
+                    &[REWARD_MINT_SEED, pool_key.as_ref(), &[reward_mint_bump]],
+                )?;
+
+                // The pool PDA becomes the reward mint authority, so it signs the initialization
+                invoke_signed(
+                    &token_program::instruction::initialize_mint(
+                        *token_program.key,
+                        *reward_mint.key,
+                        *pool_ai.key,
+                    )?,
+                    &[token_program.clone(), reward_mint.clone(), pool_ai.clone(), token_config.clone()],
+                    &[pool_seeds],
+                )?;
+                invoke(
+                    &token_program::instruction::initialize_token_account(
+                        *token_program.key,
+                        *vault_ai.key,
+                        *stake_mint.key,
+                        *pool_ai.key,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        vault_ai.clone(),
+                        stake_mint.clone(),
+                        pool_ai.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+
+                let pool = StakingPool {
+                    creator: *creator.key,
+                    token_program: *token_program.key,
+                    stake_mint: *stake_mint.key,
+                    reward_mint: *reward_mint.key,
+                    vault: *vault_ai.key,
+                    reward_rate,
+                    total_staked: 0,
+                    reward_per_token_stored: 0,
+                    last_update_time: Clock::get()?.unix_timestamp,
+                    bump,
+                };
+                pool.serialize(&mut *pool_ai.data.borrow_mut())?;
+            }
+            StakingInstruction::Stake {amount} => {
+                msg!("Instruction: Stake");
+                let user = next_account_info(accounts_iter)?;
+                let pool_ai = next_account_info(accounts_iter)?;
+                let user_stake_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let user_token = next_account_info(accounts_iter)?;
+                let stake_mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    user.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "User must sign."
+                )?;
+                let mut pool = load_pool(program_id, pool_ai)?;
+                assert_with_msg(
+                    pool.vault == *vault_ai.key
+                        && pool.stake_mint == *stake_mint.key
+                        && pool.token_program == *token_program.key,
+                    StakingError::PoolMismatch.into(),
+                    "Accounts do not match the pool"
+                )?;
+
+                // First stake of this user in this pool
+                if user_stake_ai.data_is_empty() {
+                    assert_with_msg(
+                        *system_program.key == SYSTEM_PROGRAM_ID,
+                        ProgramError::InvalidArgument,
+                        "Invalid passed in for system programs"
+                    )?;
+                    let (user_stake_key, user_stake_bump) = Pubkey::find_program_address(
+                        &[STAKE_SEED, pool_ai.key.as_ref(), user.key.as_ref()],
+                        program_id,
+                    );
+                    assert_with_msg(
+                        user_stake_key == *user_stake_ai.key,
+                        ProgramError::InvalidArgument,
+                        "Invalid PDA seeds for user stake"
+                    )?;
+                    create_pda_account(
+                        user,
+                        user_stake_ai,
+                        UserStake::LEN,
+                        program_id,
+                        system_program,
+                        &[STAKE_SEED, pool_ai.key.as_ref(), user.key.as_ref(), &[user_stake_bump]],
+                    )?;
+                    let user_stake = UserStake {
+                        owner: *user.key,
+                        pool: *pool_ai.key,
+                        amount: 0,
+                        reward_per_token_paid: 0,
+                        rewards_owed: 0,
+                        bump: user_stake_bump,
+                    };
+                    user_stake.serialize(&mut *user_stake_ai.data.borrow_mut())?;
+                }
+                let mut user_stake = load_user_stake(program_id, user_stake_ai, pool_ai, user)?;
+
+                pool.update(Clock::get()?.unix_timestamp)?;
+                user_stake.update(&pool)?;
+
+                invoke(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *user_token.key,
+                        *vault_ai.key,
+                        *user.key,
+                        *stake_mint.key,
+                        amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        user_token.clone(),
+                        vault_ai.clone(),
+                        user.clone(),
+                        stake_mint.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+
+                user_stake.amount = user_stake.amount
+                    .checked_add(amount)
+                    .ok_or(StakingError::MathOverflow)?;
+                pool.total_staked = pool.total_staked
+                    .checked_add(amount)
+                    .ok_or(StakingError::MathOverflow)?;
+                msg!("Staked {}, total staked {}", amount, pool.total_staked);
+                user_stake.serialize(&mut *user_stake_ai.data.borrow_mut())?;
+                pool.serialize(&mut *pool_ai.data.borrow_mut())?;
+            }
+            StakingInstruction::Unstake {amount} => {
+                msg!("Instruction: Unstake");
+                let user = next_account_info(accounts_iter)?;
+                let pool_ai = next_account_info(accounts_iter)?;
+                let user_stake_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let user_token = next_account_info(accounts_iter)?;
+                let stake_mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    user.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "User must sign."
+                )?;
+                let mut pool = load_pool(program_id, pool_ai)?;
+                assert_with_msg(
+                    pool.vault == *vault_ai.key
+                        && pool.stake_mint == *stake_mint.key
+                        && pool.token_program == *token_program.key,
+                    StakingError::PoolMismatch.into(),
+                    "Accounts do not match the pool"
+                )?;
+                let mut user_stake = load_user_stake(program_id, user_stake_ai, pool_ai, user)?;
+                assert_with_msg(
+                    user_stake.amount >= amount,
+                    StakingError::InsufficientStake.into(),
+                    "Cannot unstake more than the staked amount"
+                )?;
+
+                pool.update(Clock::get()?.unix_timestamp)?;
+                user_stake.update(&pool)?;
+
+                invoke_signed(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *vault_ai.key,
+                        *user_token.key,
+                        *pool_ai.key,
+                        *stake_mint.key,
+                        amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        vault_ai.clone(),
+                        user_token.clone(),
+                        pool_ai.clone(),
+                        stake_mint.clone(),
+                        token_config.clone(),
+                    ],
+                    &[&[POOL_SEED, pool.stake_mint.as_ref(), pool.creator.as_ref(), &[pool.bump]]],
+                )?;
+
+                user_stake.amount -= amount;
+                pool.total_staked -= amount;
+                msg!("Unstaked {}, total staked {}", amount, pool.total_staked);
+                user_stake.serialize(&mut *user_stake_ai.data.borrow_mut())?;
+                pool.serialize(&mut *pool_ai.data.borrow_mut())?;
+            }
+            StakingInstruction::ClaimRewards => {
+                msg!("Instruction: ClaimRewards");
+                let user = next_account_info(accounts_iter)?;
+                let pool_ai = next_account_info(accounts_iter)?;
+                let user_stake_ai = next_account_info(accounts_iter)?;
+                let reward_mint = next_account_info(accounts_iter)?;
+                let user_reward_token = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    user.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "User must sign."
+                )?;
+                let mut pool = load_pool(program_id, pool_ai)?;
+                assert_with_msg(
+                    pool.reward_mint == *reward_mint.key && pool.token_program == *token_program.key,
+                    StakingError::PoolMismatch.into(),
+                    "Accounts do not match the pool"
+                )?;
+                let mut user_stake = load_user_stake(program_id, user_stake_ai, pool_ai, user)?;
+
+                pool.update(Clock::get()?.unix_timestamp)?;
+                user_stake.update(&pool)?;
+
+                let rewards = user_stake.rewards_owed;
+                if rewards > 0 {
+                    invoke_signed(
+                        &token_program::instruction::mint_to(
+                            *token_program.key,
+                            *user_reward_token.key,
+                            *reward_mint.key,
+                            *pool_ai.key,
+                            rewards,
+                        )?,
+                        &[
+                            token_program.clone(),
+                            user_reward_token.clone(),
+                            reward_mint.clone(),
+                            pool_ai.clone(),
+                            token_config.clone(),
+                        ],
+                        &[&[POOL_SEED, pool.stake_mint.as_ref(), pool.creator.as_ref(), &[pool.bump]]],
+                    )?;
+                }
+
+                user_stake.rewards_owed = 0;
+                msg!("Claimed {} rewards", rewards);
+                user_stake.serialize(&mut *user_stake_ai.data.borrow_mut())?;
+                pool.serialize(&mut *pool_ai.data.borrow_mut())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::error::StakingError;
+
+pub const POOL_SEED: &[u8] = b"pool";
+pub const VAULT_SEED: &[u8] = b"vault";
+pub const REWARD_MINT_SEED: &[u8] = b"reward_mint";
+pub const STAKE_SEED: &[u8] = b"stake";
+
+// reward_per_token is scaled by this factor to keep precision with integer math
+pub const PRECISION: u128 = 1_000_000_000_000;
+
+/* A pool is a PDA seeded by [POOL_SEED, stake_mint, creator].
+ It owns the vault holding the staked tokens, seeded by [VAULT_SEED, pool],
+ and is the authority of the reward mint, seeded by [REWARD_MINT_SEED, pool].
+ reward_rate tokens are distributed every second among the stakers pro rata.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct StakingPool {
+    pub creator: Pubkey,
+    pub token_program: Pubkey,
+    pub stake_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub vault: Pubkey,
+    pub reward_rate: u64, // reward tokens per second for the whole pool
+    pub total_staked: u64,
+    pub reward_per_token_stored: u128, // scaled by PRECISION
+    pub last_update_time: i64,
+    pub bump: u8,
+}
+
+impl StakingPool {
+    pub const LEN: usize = 32 * 5 + 8 + 8 + 16 + 8 + 1;
+
+    pub fn reward_per_token(&self, now: i64) -> Result<u128, ProgramError> {
+        if self.total_staked == 0 {
+            return Ok(self.reward_per_token_stored);
+        }
+        let elapsed = now.saturating_sub(self.last_update_time).max(0) as u128;
+        elapsed
+            .checked_mul(self.reward_rate as u128)
+            .and_then(|n| n.checked_mul(PRECISION))
+            .map(|n| n / self.total_staked as u128)
+            .and_then(|n| n.checked_add(self.reward_per_token_stored))
+            .ok_or_else(|| StakingError::MathOverflow.into())
+    }
+
+    // Checkpoints the rewards accrued so far, must run before total_staked changes
+    pub fn update(&mut self, now: i64) -> Result<(), ProgramError> {
+        self.reward_per_token_stored = self.reward_per_token(now)?;
+        self.last_update_time = now;
+        Ok(())
+    }
+}
+
+// Per user stake, a PDA seeded by [STAKE_SEED, pool, owner]
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct UserStake {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub reward_per_token_paid: u128, // pool reward_per_token at the last checkpoint
+    pub rewards_owed: u64, // accrued and not yet claimed
+    pub bump: u8,
+}
+
+impl UserStake {
+    pub const LEN: usize = 32 * 2 + 8 + 16 + 8 + 1;
+
+    // Moves the rewards earned since the last checkpoint into rewards_owed.
+    // The pool must have been updated first
+    pub fn update(&mut self, pool: &StakingPool) -> Result<(), ProgramError> {
+        let earned = pool
+            .reward_per_token_stored
+            .checked_sub(self.reward_per_token_paid)
+            .and_then(|n| n.checked_mul(self.amount as u128))
+            .map(|n| n / PRECISION)
+            .and_then(|n| u64::try_from(n).ok())
+            .and_then(|n| n.checked_add(self.rewards_owed))
+            .ok_or(StakingError::MathOverflow)?;
+        self.rewards_owed = earned;
+        self.reward_per_token_paid = pool.reward_per_token_stored;
+        Ok(())
+    }
+}
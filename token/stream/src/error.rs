@@ -0,0 +1,22 @@
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum StreamError {
+    #[error("Instruction not implemented.")]
+    NotImplemented,
+    #[error("Account does not match the stream.")]
+    StreamMismatch,
+    #[error("Stream end time must be after its start time.")]
+    InvalidSchedule,
+    #[error("Nothing to withdraw yet.")]
+    NothingToWithdraw,
+    #[error("Math overflow.")]
+    MathOverflow,
+}
+
+impl From<StreamError> for ProgramError {
+    fn from(e: StreamError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
@@ -0,0 +1,16 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum StreamInstruction {
+    // Creates the stream and its vault, then moves amount from the sender into the vault
+    // Accounts: [sender, stream, vault, sender token account, recipient, mint,
+    //  token program, token program config, system program]
+    Create {seed: u64, amount: u64, start_time: i64, end_time: i64},
+    // Sends everything accrued and not yet withdrawn to the recipient
+    // Accounts: [recipient, stream, vault, recipient token account, mint, token program, token program config]
+    Withdraw,
+    // Pays the recipient what has accrued, returns the rest to the sender and closes the stream
+    // Accounts: [sender, stream, vault, sender token account, recipient token account, mint,
+    //  token program, token program config]
+    Cancel,
+}
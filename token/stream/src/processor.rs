@@ -0,0 +1,307 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+use token_program::state::TokenAccount;
+use token_program::utils::assert_token_program;
+
+use crate::error::StreamError;
+use crate::instruction::StreamInstruction;
+use crate::state::{Stream, STREAM_SEED, VAULT_SEED};
+
+pub fn assert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
+    if !statement {
+        msg!(msg);
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+fn close_account(ai: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let lamports = ai.lamports();
+    **destination.lamports.borrow_mut() = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    **ai.lamports.borrow_mut() = 0;
+    ai.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+fn load_stream(program_id: &Pubkey, stream_ai: &AccountInfo) -> Result<Stream, ProgramError> {
+    assert_with_msg(
+        stream_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Stream must be owned by the stream program"
+    )?;
+    Ok(Stream::try_from_slice(&stream_ai.data.borrow())?)
+}
+
+// Moves amount out of the vault, signed by the stream PDA
+#[allow(clippy::too_many_arguments)]
+fn transfer_from_vault<'a>(
+    stream: &Stream,
+    stream_ai: &AccountInfo<'a>,
+    vault_ai: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    token_config: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    invoke_signed(
+        &token_program::instruction::transfer(
+            *token_program.key,
+            *vault_ai.key,
+            *destination.key,
+            *stream_ai.key,
+            *mint.key,
+            amount,
+        )?,
+        &[
+            token_program.clone(),
+            vault_ai.clone(),
+            destination.clone(),
+            stream_ai.clone(),
+            mint.clone(),
+            token_config.clone(),
+        ],
+        &[&[STREAM_SEED, stream.sender.as_ref(), &stream.seed.to_le_bytes(), &[stream.bump]]],
+    )
+}
+
+/* Tokens accrue linearly between start_time and end_time, read from the Clock sysvar.
+   Nothing accrues before start_time and the full deposit has accrued at end_time.
+   As in the escrow program, the vault is left empty once the stream is cancelled,
+   since token_program has no instruction to close a token account.
+*/
+pub struct Processor {}
+
+impl Processor {
+    pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8]
+    ) -> ProgramResult {
+        let instruction = StreamInstruction::try_from_slice(instruction_data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        let accounts_iter = &mut accounts.iter();
+
+        match instruction {
+            StreamInstruction::Create {seed, amount, start_time, end_time} => {
+                msg!("Instruction: Create");
+                let sender = next_account_info(accounts_iter)?;
+                let stream_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let sender_token = next_account_info(accounts_iter)?;
+                let recipient = next_account_info(accounts_iter)?;
+                let mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    sender.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Sender must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                assert_with_msg(
+                    end_time > start_time,
+                    StreamError::InvalidSchedule.into(),
+                    "End time must be after start time"
+                )?;
+
+                let seed_bytes = seed.to_le_bytes();
+                let (stream_key, bump) = Pubkey::find_program_address(
+                    &[STREAM_SEED, sender.key.as_ref(), &seed_bytes],
+                    program_id,
+                );
+                let (vault_key, vault_bump) = Pubkey::find_program_address(
+                    &[VAULT_SEED, stream_key.as_ref()],
+                    program_id,
+                );
+                assert_with_msg(
+                    stream_key == *stream_ai.key && vault_key == *vault_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for stream accounts"
+                )?;
+
+                let rent = Rent::get()?;
+                invoke_signed(
+                    &system_instruction::create_account(
+                        sender.key,
+                        stream_ai.key,
+                        rent.minimum_balance(Stream::LEN),
+                        Stream::LEN as u64,
+                        program_id,
+                    ),
+                    &[sender.clone(), stream_ai.clone(), system_program.clone()],
+                    &[&[STREAM_SEED, sender.key.as_ref(), &seed_bytes, &[bump]]],
+                )?;
+                invoke_signed(
+                    &system_instruction::create_account(
+                        sender.key,
+                        vault_ai.key,
+                        rent.minimum_balance(TokenAccount::LEN),
+                        TokenAccount::LEN as u64,
+                        token_program.key,
+                    ),
+                    &[sender.clone(), vault_ai.clone(), system_program.clone()],
+                    &[&[VAULT_SEED, stream_key.as_ref(), &[vault_bump]]],
+                )?;
+                invoke(
+                    &token_program::instruction::initialize_token_account(
+                        *token_program.key,
+                        *vault_ai.key,
+                        *mint.key,
+                        *stream_ai.key,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        vault_ai.clone(),
+                        mint.clone(),
+                        stream_ai.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+                invoke(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *sender_token.key,
+                        *vault_ai.key,
+                        *sender.key,
+                        *mint.key,
+                        amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        sender_token.clone(),
+                        vault_ai.clone(),
+                        sender.clone(),
+                        mint.clone(),
+                        token_config.clone(),
+                    ],
+                )?;
+
+                let stream = Stream {
+                    sender: *sender.key,
+                    recipient: *recipient.key,
+                    token_program: *token_program.key,
+                    mint: *mint.key,
+                    vault: *vault_ai.key,
+                    deposited: amount,
+                    withdrawn: 0,
+                    start_time,
+                    end_time,
+                    seed,
+                    bump,
+                };
+                stream.serialize(&mut *stream_ai.data.borrow_mut())?;
+            }
+            StreamInstruction::Withdraw => {
+                msg!("Instruction: Withdraw");
+                let recipient = next_account_info(accounts_iter)?;
+                let stream_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let recipient_token = next_account_info(accounts_iter)?;
+                let mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    recipient.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Recipient must sign."
+                )?;
+                let mut stream = load_stream(program_id, stream_ai)?;
+                assert_with_msg(
+                    stream.vault == *vault_ai.key
+                        && stream.recipient == *recipient.key
+                        && stream.mint == *mint.key
+                        && stream.token_program == *token_program.key,
+                    StreamError::StreamMismatch.into(),
+                    "Accounts do not match the stream"
+                )?;
+
+                let amount = stream.withdrawable_at(Clock::get()?.unix_timestamp);
+                assert_with_msg(
+                    amount > 0,
+                    StreamError::NothingToWithdraw.into(),
+                    "Nothing has accrued since the last withdrawal"
+                )?;
+                stream.withdrawn = stream.withdrawn
+                    .checked_add(amount)
+                    .ok_or(StreamError::MathOverflow)?;
+
+                transfer_from_vault(&stream, stream_ai, vault_ai, recipient_token, mint, token_program, token_config, amount)?;
+
+                msg!("Withdrew {}, {} of {} withdrawn", amount, stream.withdrawn, stream.deposited);
+                stream.serialize(&mut *stream_ai.data.borrow_mut())?;
+            }
+            StreamInstruction::Cancel => {
+                msg!("Instruction: Cancel");
+                let sender = next_account_info(accounts_iter)?;
+                let stream_ai = next_account_info(accounts_iter)?;
+                let vault_ai = next_account_info(accounts_iter)?;
+                let sender_token = next_account_info(accounts_iter)?;
+                let recipient_token = next_account_info(accounts_iter)?;
+                let mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    sender.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Sender must sign."
+                )?;
+                let stream = load_stream(program_id, stream_ai)?;
+                assert_with_msg(
+                    stream.vault == *vault_ai.key
+                        && stream.sender == *sender.key
+                        && stream.mint == *mint.key
+                        && stream.token_program == *token_program.key,
+                    StreamError::StreamMismatch.into(),
+                    "Accounts do not match the stream"
+                )?;
+
+                // The token program checks that both destinations hold the streamed mint
+                let owed = stream.withdrawable_at(Clock::get()?.unix_timestamp);
+                if owed > 0 {
+                    assert_with_msg(
+                        TokenAccount::load(recipient_token)?.owner == stream.recipient,
+                        ProgramError::InvalidArgument,
+                        "Recipient token account must be owned by the recipient"
+                    )?;
+                    transfer_from_vault(&stream, stream_ai, vault_ai, recipient_token, mint, token_program, token_config, owed)?;
+                }
+                let refund = TokenAccount::load(vault_ai)?.amount;
+                if refund > 0 {
+                    transfer_from_vault(&stream, stream_ai, vault_ai, sender_token, mint, token_program, token_config, refund)?;
+                }
+
+                msg!("Cancelled stream, {} to recipient and {} refunded", owed, refund);
+                close_account(stream_ai, sender)?;
+            }
+        }
+
+        Ok(())
+    }
+}
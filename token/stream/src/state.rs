@@ -0,0 +1,46 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+pub const STREAM_SEED: &[u8] = b"stream";
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/* A stream is a PDA seeded by [STREAM_SEED, sender, seed].
+ It owns the vault, a token_program TokenAccount that is itself a PDA seeded by
+ [VAULT_SEED, stream], holding the deposit that accrues linearly to the recipient
+ between start_time and end_time.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Stream {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub token_program: Pubkey, // token program the vault and every token account belong to
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub deposited: u64,
+    pub withdrawn: u64, // already paid out to the recipient
+    pub start_time: i64,
+    pub end_time: i64,
+    pub seed: u64, // lets a sender have several open streams
+    pub bump: u8,
+}
+
+impl Stream {
+    pub const LEN: usize = 32 * 5 + 8 * 5 + 1;
+
+    // Amount accrued to the recipient at `now`, withdrawn or not
+    pub fn accrued_at(&self, now: i64) -> u64 {
+        if now <= self.start_time {
+            0
+        } else if now >= self.end_time {
+            self.deposited
+        } else {
+            let elapsed = (now - self.start_time) as u128;
+            let duration = (self.end_time - self.start_time) as u128;
+            (self.deposited as u128 * elapsed / duration) as u64
+        }
+    }
+
+    pub fn withdrawable_at(&self, now: i64) -> u64 {
+        self.accrued_at(now).saturating_sub(self.withdrawn)
+    }
+}
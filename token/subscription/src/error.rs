@@ -0,0 +1,26 @@
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum SubscriptionError {
+    #[error("Instruction not implemented.")]
+    NotImplemented,
+    #[error("Account does not match the subscription.")]
+    SubscriptionMismatch,
+    #[error("Billing period must be positive.")]
+    InvalidPeriod,
+    #[error("Billing period has not elapsed yet.")]
+    NotDue,
+    #[error("Math overflow.")]
+    MathOverflow,
+    #[error("At least one billing period must be approved.")]
+    NoPeriodApproved,
+    #[error("Token account already has another delegate.")]
+    DelegateInUse,
+}
+
+impl From<SubscriptionError> for ProgramError {
+    fn from(e: SubscriptionError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
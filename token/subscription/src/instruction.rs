@@ -0,0 +1,21 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum SubscriptionInstruction {
+    // Registers the plan and approves the subscription PDA as delegate of the subscriber token account
+    // for amount over the next periods periods. The first period can be charged right away.
+    // Fails if the token account already has another delegate, e.g. another subscription
+    // Accounts: [subscriber, subscription, subscriber token account, merchant token account, mint,
+    //  token program, token program config, system program]
+    Subscribe {seed: u64, amount: u64, period: i64, periods: u32},
+    // Permissionless crank transferring amount to the merchant once the period has elapsed
+    // Accounts: [subscription, subscriber token account, merchant token account, mint,
+    //  token program, token program config]
+    Charge,
+    // Approves amount for periods more periods, on top of what is left of the current approval
+    // Accounts: [subscriber, subscription, subscriber token account, token program, token program config]
+    Renew {periods: u32},
+    // Revokes the delegation and closes the subscription
+    // Accounts: [subscriber, subscription, subscriber token account, token program, token program config]
+    Cancel,
+}
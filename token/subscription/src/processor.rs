@@ -0,0 +1,351 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
+};
+use token_program::state::TokenAccount;
+use token_program::utils::assert_token_program;
+
+use crate::error::SubscriptionError;
+use crate::instruction::SubscriptionInstruction;
+use crate::state::{Subscription, SUBSCRIPTION_SEED};
+
+pub fn assert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
+    if !statement {
+        msg!(msg);
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+fn close_account(ai: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let lamports = ai.lamports();
+    **destination.lamports.borrow_mut() = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    **ai.lamports.borrow_mut() = 0;
+    ai.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+fn load_subscription(program_id: &Pubkey, subscription_ai: &AccountInfo) -> Result<Subscription, ProgramError> {
+    assert_with_msg(
+        subscription_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Subscription must be owned by the subscription program"
+    )?;
+    Ok(Subscription::try_from_slice(&subscription_ai.data.borrow())?)
+}
+
+// Approves the subscription PDA for allowance, signed by the subscriber
+fn approve_subscription<'a>(
+    subscription_ai: &AccountInfo<'a>,
+    subscriber: &AccountInfo<'a>,
+    subscriber_token: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    token_config: &AccountInfo<'a>,
+    allowance: u64,
+) -> ProgramResult {
+    // The token program checks that the subscriber owns the token account
+    invoke(
+        &token_program::instruction::approve(
+            *token_program.key,
+            *subscriber_token.key,
+            *subscription_ai.key,
+            *subscriber.key,
+            allowance,
+        )?,
+        &[
+            token_program.clone(),
+            subscriber_token.clone(),
+            subscription_ai.clone(),
+            subscriber.clone(),
+            token_config.clone(),
+        ],
+    )
+}
+
+/* The subscription never holds tokens. Subscribe approves the subscription PDA as
+   delegate of the subscriber token account for the number of periods the subscriber
+   chose, and Charge moves funds through token_program Transfer signed by the PDA,
+   which only this program can do and only for amount once per period.
+   Approving replaces the previous delegate of the token account, so Subscribe and Renew
+   refuse to take over a delegation made to someone else.
+   The subscriber can stop payments at any time with Cancel, or directly with
+   token_program Revoke.
+*/
+pub struct Processor {}
+
+impl Processor {
+    pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8]
+    ) -> ProgramResult {
+        let instruction = SubscriptionInstruction::try_from_slice(instruction_data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        let accounts_iter = &mut accounts.iter();
+
+        match instruction {
+            SubscriptionInstruction::Subscribe {seed, amount, period, periods} => {
+                msg!("Instruction: Subscribe");
+                let subscriber = next_account_info(accounts_iter)?;
+                let subscription_ai = next_account_info(accounts_iter)?;
+                let subscriber_token = next_account_info(accounts_iter)?;
+                let merchant_token = next_account_info(accounts_iter)?;
+                let mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    subscriber.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Subscriber must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                assert_with_msg(
+                    period > 0,
+                    SubscriptionError::InvalidPeriod.into(),
+                    "Billing period must be positive"
+                )?;
+                assert_with_msg(
+                    subscriber_token.owner == token_program.key && merchant_token.owner == token_program.key,
+                    ProgramError::IncorrectProgramId,
+                    "Token accounts must belong to the token program"
+                )?;
+                let merchant_account = TokenAccount::load(merchant_token)?;
+                let subscriber_account = TokenAccount::load(subscriber_token)?;
+                assert_with_msg(
+                    subscriber_account.mint == *mint.key && merchant_account.mint == *mint.key,
+                    ProgramError::InvalidAccountData,
+                    "Token accounts must hold the subscription mint"
+                )?;
+                assert_with_msg(
+                    periods > 0,
+                    SubscriptionError::NoPeriodApproved.into(),
+                    "At least one billing period must be approved"
+                )?;
+                let allowance = amount.checked_mul(periods as u64).ok_or(SubscriptionError::MathOverflow)?;
+
+                let seed_bytes = seed.to_le_bytes();
+                let (subscription_key, bump) = Pubkey::find_program_address(
+                    &[SUBSCRIPTION_SEED, subscriber.key.as_ref(), &seed_bytes],
+                    program_id,
+                );
+                assert_with_msg(
+                    subscription_key == *subscription_ai.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for subscription"
+                )?;
+                assert_with_msg(
+                    subscriber_account.delegate.is_none() || subscriber_account.delegate == Some(subscription_key),
+                    SubscriptionError::DelegateInUse.into(),
+                    "Subscriber token account already has another delegate"
+                )?;
+
+                invoke_signed(
+                    &system_instruction::create_account(
+                        subscriber.key,
+                        subscription_ai.key,
+                        Rent::get()?.minimum_balance(Subscription::LEN),
+                        Subscription::LEN as u64,
+                        program_id,
+                    ),
+                    &[subscriber.clone(), subscription_ai.clone(), system_program.clone()],
+                    &[&[SUBSCRIPTION_SEED, subscriber.key.as_ref(), &seed_bytes, &[bump]]],
+                )?;
+                approve_subscription(subscription_ai, subscriber, subscriber_token, token_program, token_config, allowance)?;
+
+                let subscription = Subscription {
+                    subscriber: *subscriber.key,
+                    token_program: *token_program.key,
+                    mint: *mint.key,
+                    subscriber_token: *subscriber_token.key,
+                    merchant_token: *merchant_token.key,
+                    amount,
+                    period,
+                    next_charge_at: Clock::get()?.unix_timestamp,
+                    seed,
+                    bump,
+                };
+                msg!(
+                    "Subscribed to {} every {} seconds for {} periods, paid to {}",
+                    amount,
+                    period,
+                    periods,
+                    merchant_account.owner
+                );
+                subscription.serialize(&mut *subscription_ai.data.borrow_mut())?;
+            }
+            SubscriptionInstruction::Charge => {
+                msg!("Instruction: Charge");
+                let subscription_ai = next_account_info(accounts_iter)?;
+                let subscriber_token = next_account_info(accounts_iter)?;
+                let merchant_token = next_account_info(accounts_iter)?;
+                let mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                let mut subscription = load_subscription(program_id, subscription_ai)?;
+                assert_with_msg(
+                    subscription.subscriber_token == *subscriber_token.key
+                        && subscription.merchant_token == *merchant_token.key
+                        && subscription.mint == *mint.key
+                        && subscription.token_program == *token_program.key,
+                    SubscriptionError::SubscriptionMismatch.into(),
+                    "Accounts do not match the subscription"
+                )?;
+
+                let now = Clock::get()?.unix_timestamp;
+                assert_with_msg(
+                    now >= subscription.next_charge_at,
+                    SubscriptionError::NotDue.into(),
+                    "Billing period has not elapsed"
+                )?;
+                // Periods nobody cranked are not billed afterwards
+                let next_charge_at = subscription.next_charge_at
+                    .checked_add(subscription.period)
+                    .ok_or(SubscriptionError::MathOverflow)?;
+                subscription.next_charge_at = if next_charge_at > now {
+                    next_charge_at
+                } else {
+                    now.checked_add(subscription.period).ok_or(SubscriptionError::MathOverflow)?
+                };
+
+                // Fails if the subscriber revoked the delegation, used up the approved periods or lacks funds
+                invoke_signed(
+                    &token_program::instruction::transfer(
+                        *token_program.key,
+                        *subscriber_token.key,
+                        *merchant_token.key,
+                        *subscription_ai.key,
+                        *mint.key,
+                        subscription.amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        subscriber_token.clone(),
+                        merchant_token.clone(),
+                        subscription_ai.clone(),
+                        mint.clone(),
+                        token_config.clone(),
+                    ],
+                    &[&[
+                        SUBSCRIPTION_SEED,
+                        subscription.subscriber.as_ref(),
+                        &subscription.seed.to_le_bytes(),
+                        &[subscription.bump],
+                    ]],
+                )?;
+
+                msg!("Charged {}, next charge at {}", subscription.amount, subscription.next_charge_at);
+                subscription.serialize(&mut *subscription_ai.data.borrow_mut())?;
+            }
+            SubscriptionInstruction::Renew {periods} => {
+                msg!("Instruction: Renew");
+                let subscriber = next_account_info(accounts_iter)?;
+                let subscription_ai = next_account_info(accounts_iter)?;
+                let subscriber_token = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    subscriber.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Subscriber must sign."
+                )?;
+                assert_with_msg(
+                    periods > 0,
+                    SubscriptionError::NoPeriodApproved.into(),
+                    "At least one billing period must be approved"
+                )?;
+                let subscription = load_subscription(program_id, subscription_ai)?;
+                assert_with_msg(
+                    subscription.subscriber == *subscriber.key
+                        && subscription.subscriber_token == *subscriber_token.key
+                        && subscription.token_program == *token_program.key,
+                    SubscriptionError::SubscriptionMismatch.into(),
+                    "Accounts do not match the subscription"
+                )?;
+
+                let subscriber_account = TokenAccount::load(subscriber_token)?;
+                let remaining = match subscriber_account.delegate {
+                    Some(delegate) if delegate == *subscription_ai.key => subscriber_account.delegated_amount,
+                    None => 0,
+                    Some(_) => {
+                        msg!("Subscriber token account already has another delegate");
+                        return Err(SubscriptionError::DelegateInUse.into())
+                    }
+                };
+                let allowance = subscription.amount
+                    .checked_mul(periods as u64)
+                    .and_then(|allowance| allowance.checked_add(remaining))
+                    .ok_or(SubscriptionError::MathOverflow)?;
+                approve_subscription(subscription_ai, subscriber, subscriber_token, token_program, token_config, allowance)?;
+                msg!("Approved {} for the next charges", allowance);
+            }
+            SubscriptionInstruction::Cancel => {
+                msg!("Instruction: Cancel");
+                let subscriber = next_account_info(accounts_iter)?;
+                let subscription_ai = next_account_info(accounts_iter)?;
+                let subscriber_token = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    subscriber.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Subscriber must sign."
+                )?;
+                let subscription = load_subscription(program_id, subscription_ai)?;
+                assert_with_msg(
+                    subscription.subscriber == *subscriber.key
+                        && subscription.subscriber_token == *subscriber_token.key
+                        && subscription.token_program == *token_program.key,
+                    SubscriptionError::SubscriptionMismatch.into(),
+                    "Accounts do not match the subscription"
+                )?;
+
+                // Leave any delegation the subscriber made since then untouched
+                if TokenAccount::load(subscriber_token)?.delegate == Some(*subscription_ai.key) {
+                    invoke(
+                        &token_program::instruction::revoke(
+                            *token_program.key,
+                            *subscriber_token.key,
+                            *subscriber.key,
+                        )?,
+                        &[
+                            token_program.clone(),
+                            subscriber_token.clone(),
+                            subscriber.clone(),
+                            token_config.clone(),
+                        ],
+                    )?;
+                }
+
+                close_account(subscription_ai, subscriber)?;
+            }
+        }
+
+        Ok(())
+    }
+}
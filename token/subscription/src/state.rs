@@ -0,0 +1,28 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+pub const SUBSCRIPTION_SEED: &[u8] = b"subscription";
+
+/* A subscription is a PDA seeded by [SUBSCRIPTION_SEED, subscriber, seed].
+ It is the approved delegate of the subscriber's token_program TokenAccount,
+ which lets it pull amount into the merchant's TokenAccount once per period,
+ for as many periods as the subscriber approved.
+ A token account has a single delegate, so each subscription needs its own token account.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Subscription {
+    pub subscriber: Pubkey,
+    pub token_program: Pubkey, // token program both token accounts belong to
+    pub mint: Pubkey,
+    pub subscriber_token: Pubkey, // account charged, delegated to the subscription
+    pub merchant_token: Pubkey, // account receiving the payments
+    pub amount: u64, // charged every period
+    pub period: i64, // seconds
+    pub next_charge_at: i64, // unix timestamp from which Charge can be cranked again
+    pub seed: u64, // lets a subscriber have several subscriptions, paid from different token accounts
+    pub bump: u8,
+}
+
+impl Subscription {
+    pub const LEN: usize = 32 * 5 + 8 * 4 + 1;
+}
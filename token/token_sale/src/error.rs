@@ -0,0 +1,36 @@
+use solana_program::program_error::ProgramError;
+use thiserror::Error;
+
+#[derive(Error, Debug, Copy, Clone, PartialEq)]
+pub enum SaleError {
+    #[error("Instruction not implemented.")]
+    NotImplemented,
+    #[error("Account does not match the sale.")]
+    SaleMismatch,
+    #[error("Sale has not started.")]
+    NotStarted,
+    #[error("Sale has ended.")]
+    Ended,
+    #[error("Sale has not ended.")]
+    NotEnded,
+    #[error("Purchase exceeds the sale hard cap.")]
+    HardCapExceeded,
+    #[error("Purchase exceeds the per buyer cap.")]
+    BuyerCapExceeded,
+    #[error("Soft cap reached, refunds are disabled.")]
+    SoftCapReached,
+    #[error("Soft cap not reached, proceeds are locked.")]
+    SoftCapNotReached,
+    #[error("Proceeds already withdrawn.")]
+    AlreadyWithdrawn,
+    #[error("Numerical overflow.")]
+    MathOverflow,
+    #[error("Buyer holds none of the tokens bought.")]
+    NothingToRefund,
+}
+
+impl From<SaleError> for ProgramError {
+    fn from(e: SaleError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
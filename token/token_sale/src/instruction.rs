@@ -0,0 +1,34 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+/* Sales paid with a quote token take the quote accounts listed in parentheses
+ at the end of the account list, lamport sales omit them.
+*/
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum SaleInstruction {
+    // Creates the sale and its mint, and the quote vault for quote token sales
+    // Accounts: [creator, sale, mint, token program, token program config, system program,
+    //  (quote mint, quote vault)]
+    InitializeSale {
+        id: u64,
+        quote_mint: Option<Pubkey>,
+        price: u64,
+        start_time: i64,
+        end_time: i64,
+        soft_cap: u64,
+        hard_cap: u64,
+        per_buyer_cap: u64,
+    },
+    // Accounts: [buyer, sale, purchase, mint, buyer token account, token program,
+    //  token program config, system program, (buyer quote token account, quote vault, quote mint)]
+    Buy {amount: u64},
+    // Once the sale ended below its soft cap, burns the tokens bought that the buyer token account
+    // still holds and refunds the matching share of the payment
+    // Accounts: [buyer, sale, purchase, mint, buyer token account, token program,
+    //  token program config, (buyer quote token account, quote vault, quote mint)]
+    Refund,
+    // Once the sale ended above its soft cap, sends the proceeds to the creator
+    // Accounts: [creator, sale, token program, token program config,
+    //  (creator quote token account, quote vault, quote mint)]
+    Withdraw,
+}
@@ -0,0 +1,526 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{clock::Clock, Sysvar},
+};
+use token_program::state::{Mint, TokenAccount};
+use token_program::utils::{assert_token_program, create_pda_account};
+
+use crate::error::SaleError;
+use crate::instruction::SaleInstruction;
+use crate::state::{Purchase, Sale, MINT_SEED, PURCHASE_SEED, QUOTE_VAULT_SEED, SALE_SEED};
+
+pub fn assert_with_msg(statement: bool, err: ProgramError, msg: &str) -> ProgramResult {
+    if !statement {
+        msg!(msg);
+        Err(err)
+    } else {
+        Ok(())
+    }
+}
+
+// Debits a program owned account, used for the lamports held by the sale and purchase accounts
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    **from.lamports.borrow_mut() = from
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    **to.lamports.borrow_mut() = to
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(SaleError::MathOverflow)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn token_transfer<'a>(
+    token_program: &AccountInfo<'a>,
+    src: &AccountInfo<'a>,
+    dst: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    token_config: &AccountInfo<'a>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &token_program::instruction::transfer(
+            *token_program.key,
+            *src.key,
+            *dst.key,
+            *authority.key,
+            *mint.key,
+            amount,
+        )?,
+        &[
+            token_program.clone(),
+            src.clone(),
+            dst.clone(),
+            authority.clone(),
+            mint.clone(),
+            token_config.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+fn load_sale(program_id: &Pubkey, sale_ai: &AccountInfo) -> Result<Sale, ProgramError> {
+    assert_with_msg(
+        sale_ai.owner == program_id,
+        ProgramError::IncorrectProgramId,
+        "Sale must be owned by the sale program"
+    )?;
+    Sale::load(sale_ai)
+}
+
+// Checks the trailing quote accounts of a quote token sale
+fn assert_quote_accounts(sale: &Sale, quote_vault: &AccountInfo, quote_mint: &AccountInfo) -> ProgramResult {
+    assert_with_msg(
+        sale.quote_vault == Some(*quote_vault.key) && sale.quote_mint == Some(*quote_mint.key),
+        SaleError::SaleMismatch.into(),
+        "Quote accounts do not match the sale"
+    )
+}
+
+pub struct Processor {}
+
+impl Processor {
+    pub fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8]
+    ) -> ProgramResult {
+        let instruction = SaleInstruction::try_from_slice(instruction_data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+        let accounts_iter = &mut accounts.iter();
+
+        match instruction {
+            SaleInstruction::InitializeSale {
+                id,
+                quote_mint,
+                price,
+                start_time,
+                end_time,
+                soft_cap,
+                hard_cap,
+                per_buyer_cap,
+            } => {
+                msg!("Instruction: InitializeSale");
+                let creator = next_account_info(accounts_iter)?;
+                let sale_ai = next_account_info(accounts_iter)?;
+                let mint = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    creator.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Creator must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                assert_with_msg(
+                    start_time < end_time && soft_cap <= hard_cap && price > 0,
+                    ProgramError::InvalidArgument,
+                    "Invalid sale parameters"
+                )?;
+
+                let id_bytes = id.to_le_bytes();
+                let (sale_key, bump) = Pubkey::find_program_address(
+                    &[SALE_SEED, creator.key.as_ref(), &id_bytes],
+                    program_id,
+                );
+                let (mint_key, mint_bump) = Pubkey::find_program_address(
+                    &[MINT_SEED, sale_key.as_ref()],
+                    program_id,
+                );
+                assert_with_msg(
+                    sale_key == *sale_ai.key && mint_key == *mint.key,
+                    ProgramError::InvalidArgument,
+                    "Invalid PDA seeds for sale accounts"
+                )?;
+
+                let sale_seeds: &[&[u8]] = &[SALE_SEED, creator.key.as_ref(), &id_bytes, &[bump]];
+                create_pda_account(creator, sale_ai, Sale::LEN, program_id, system_program, sale_seeds)?;
+                create_pda_account(
+                    creator,
+                    mint,
+                    Mint::LEN,
+                    token_program.key,
+                    system_program,
+                    &[MINT_SEED, sale_key.as_ref(), &[mint_bump]],
+                )?;
+                // The sale PDA is the mint authority, so it signs the initialization
+                invoke_signed(
+                    &token_program::instruction::initialize_mint(
+                        *token_program.key,
+                        *mint.key,
+                        *sale_ai.key,
+                    )?,
+                    &[token_program.clone(), mint.clone(), sale_ai.clone(), token_config.clone()],
+                    &[sale_seeds],
+                )?;
+
+                let quote_vault = match quote_mint {
+                    Some(quote_mint_key) => {
+                        let quote_mint = next_account_info(accounts_iter)?;
+                        let quote_vault = next_account_info(accounts_iter)?;
+                        assert_with_msg(
+                            quote_mint_key == *quote_mint.key,
+                            ProgramError::InvalidArgument,
+                            "Quote mint mismatch"
+                        )?;
+                        let (quote_vault_key, quote_vault_bump) = Pubkey::find_program_address(
+                            &[QUOTE_VAULT_SEED, sale_key.as_ref()],
+                            program_id,
+                        );
+                        assert_with_msg(
+                            quote_vault_key == *quote_vault.key,
+                            ProgramError::InvalidArgument,
+                            "Invalid PDA seeds for quote vault"
+                        )?;
+                        create_pda_account(
+                            creator,
+                            quote_vault,
+                            TokenAccount::LEN,
+                            token_program.key,
+                            system_program,
+                            &[QUOTE_VAULT_SEED, sale_key.as_ref(), &[quote_vault_bump]],
+                        )?;
+                        invoke(
+                            &token_program::instruction::initialize_token_account(
+                                *token_program.key,
+                                *quote_vault.key,
+                                *quote_mint.key,
+                                *sale_ai.key,
+                            )?,
+                            &[
+                                token_program.clone(),
+                                quote_vault.clone(),
+                                quote_mint.clone(),
+                                sale_ai.clone(),
+                                token_config.clone(),
+                            ],
+                        )?;
+                        Some(quote_vault_key)
+                    }
+                    None => None,
+                };
+
+                let sale = Sale {
+                    creator: *creator.key,
+                    id,
+                    token_program: *token_program.key,
+                    mint: *mint.key,
+                    quote_mint,
+                    quote_vault,
+                    price,
+                    start_time,
+                    end_time,
+                    soft_cap,
+                    hard_cap,
+                    per_buyer_cap,
+                    total_sold: 0,
+                    total_raised: 0,
+                    withdrawn: false,
+                    bump,
+                };
+                sale.save(sale_ai)?;
+            }
+            SaleInstruction::Buy {amount} => {
+                msg!("Instruction: Buy");
+                let buyer = next_account_info(accounts_iter)?;
+                let sale_ai = next_account_info(accounts_iter)?;
+                let purchase_ai = next_account_info(accounts_iter)?;
+                let mint = next_account_info(accounts_iter)?;
+                let buyer_token = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    buyer.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Buyer must sign."
+                )?;
+                assert_with_msg(
+                    *system_program.key == SYSTEM_PROGRAM_ID,
+                    ProgramError::InvalidArgument,
+                    "Invalid passed in for system programs"
+                )?;
+                let mut sale = load_sale(program_id, sale_ai)?;
+                assert_with_msg(
+                    sale.mint == *mint.key && sale.token_program == *token_program.key,
+                    SaleError::SaleMismatch.into(),
+                    "Accounts do not match the sale"
+                )?;
+
+                let now = Clock::get()?.unix_timestamp;
+                assert_with_msg(now >= sale.start_time, SaleError::NotStarted.into(), "Sale has not started")?;
+                assert_with_msg(now < sale.end_time, SaleError::Ended.into(), "Sale has ended")?;
+
+                // First purchase of this buyer
+                if purchase_ai.data_is_empty() {
+                    let (purchase_key, purchase_bump) = Pubkey::find_program_address(
+                        &[PURCHASE_SEED, sale_ai.key.as_ref(), buyer.key.as_ref()],
+                        program_id,
+                    );
+                    assert_with_msg(
+                        purchase_key == *purchase_ai.key,
+                        ProgramError::InvalidArgument,
+                        "Invalid PDA seeds for purchase"
+                    )?;
+                    create_pda_account(
+                        buyer,
+                        purchase_ai,
+                        Purchase::LEN,
+                        program_id,
+                        system_program,
+                        &[PURCHASE_SEED, sale_ai.key.as_ref(), buyer.key.as_ref(), &[purchase_bump]],
+                    )?;
+                    let purchase = Purchase {
+                        buyer: *buyer.key,
+                        sale: *sale_ai.key,
+                        amount: 0,
+                        paid: 0,
+                        bump: purchase_bump,
+                    };
+                    purchase.serialize(&mut *purchase_ai.data.borrow_mut())?;
+                }
+                assert_with_msg(
+                    purchase_ai.owner == program_id,
+                    ProgramError::IncorrectProgramId,
+                    "Purchase must be owned by the sale program"
+                )?;
+                let mut purchase = Purchase::try_from_slice(&purchase_ai.data.borrow())?;
+                assert_with_msg(
+                    purchase.buyer == *buyer.key && purchase.sale == *sale_ai.key,
+                    SaleError::SaleMismatch.into(),
+                    "Purchase does not match the buyer and sale"
+                )?;
+
+                sale.total_sold = sale.total_sold
+                    .checked_add(amount)
+                    .filter(|total_sold| *total_sold <= sale.hard_cap)
+                    .ok_or(SaleError::HardCapExceeded)?;
+                purchase.amount = purchase.amount
+                    .checked_add(amount)
+                    .filter(|bought| *bought <= sale.per_buyer_cap)
+                    .ok_or(SaleError::BuyerCapExceeded)?;
+                let cost = amount.checked_mul(sale.price).ok_or(SaleError::MathOverflow)?;
+                purchase.paid = purchase.paid.checked_add(cost).ok_or(SaleError::MathOverflow)?;
+                sale.total_raised = sale.total_raised.checked_add(cost).ok_or(SaleError::MathOverflow)?;
+
+                match sale.quote_mint {
+                    None => invoke(
+                        &system_instruction::transfer(buyer.key, sale_ai.key, cost),
+                        &[buyer.clone(), sale_ai.clone(), system_program.clone()],
+                    )?,
+                    Some(_) => {
+                        let buyer_quote = next_account_info(accounts_iter)?;
+                        let quote_vault = next_account_info(accounts_iter)?;
+                        let quote_mint = next_account_info(accounts_iter)?;
+                        assert_quote_accounts(&sale, quote_vault, quote_mint)?;
+                        token_transfer(token_program, buyer_quote, quote_vault, buyer, quote_mint, token_config, cost, &[])?;
+                    }
+                }
+
+                invoke_signed(
+                    &token_program::instruction::mint_to(
+                        *token_program.key,
+                        *buyer_token.key,
+                        *mint.key,
+                        *sale_ai.key,
+                        amount,
+                    )?,
+                    &[
+                        token_program.clone(),
+                        buyer_token.clone(),
+                        mint.clone(),
+                        sale_ai.clone(),
+                        token_config.clone(),
+                    ],
+                    &[&[SALE_SEED, sale.creator.as_ref(), &sale.id.to_le_bytes(), &[sale.bump]]],
+                )?;
+
+                msg!("Bought {} for {}, total sold {}", amount, cost, sale.total_sold);
+                purchase.serialize(&mut *purchase_ai.data.borrow_mut())?;
+                sale.save(sale_ai)?;
+            }
+            SaleInstruction::Refund => {
+                msg!("Instruction: Refund");
+                let buyer = next_account_info(accounts_iter)?;
+                let sale_ai = next_account_info(accounts_iter)?;
+                let purchase_ai = next_account_info(accounts_iter)?;
+                let mint = next_account_info(accounts_iter)?;
+                let buyer_token = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    buyer.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Buyer must sign."
+                )?;
+                let mut sale = load_sale(program_id, sale_ai)?;
+                assert_with_msg(
+                    sale.mint == *mint.key && sale.token_program == *token_program.key,
+                    SaleError::SaleMismatch.into(),
+                    "Accounts do not match the sale"
+                )?;
+                assert_with_msg(
+                    Clock::get()?.unix_timestamp >= sale.end_time,
+                    SaleError::NotEnded.into(),
+                    "Sale has not ended"
+                )?;
+                assert_with_msg(
+                    !sale.soft_cap_reached(),
+                    SaleError::SoftCapReached.into(),
+                    "Soft cap reached, refunds are disabled"
+                )?;
+                assert_with_msg(
+                    purchase_ai.owner == program_id,
+                    ProgramError::IncorrectProgramId,
+                    "Purchase must be owned by the sale program"
+                )?;
+                let mut purchase = Purchase::try_from_slice(&purchase_ai.data.borrow())?;
+                assert_with_msg(
+                    purchase.buyer == *buyer.key && purchase.sale == *sale_ai.key,
+                    SaleError::SaleMismatch.into(),
+                    "Purchase does not match the buyer and sale"
+                )?;
+
+                // Tokens the buyer moved away are not refunded, the rest is refunded pro rata.
+                // The buyer can refund again later after getting the missing tokens back
+                let returned = std::cmp::min(TokenAccount::load(buyer_token)?.amount, purchase.amount);
+                assert_with_msg(
+                    returned > 0,
+                    SaleError::NothingToRefund.into(),
+                    "Buyer token account holds none of the tokens bought"
+                )?;
+                let refund = purchase.refund_for(returned).ok_or(SaleError::MathOverflow)?;
+                invoke(
+                    &token_program::instruction::burn(
+                        *token_program.key,
+                        *buyer_token.key,
+                        *mint.key,
+                        *buyer.key,
+                        returned,
+                    )?,
+                    &[token_program.clone(), buyer_token.clone(), mint.clone(), buyer.clone()],
+                )?;
+
+                match sale.quote_mint {
+                    None => move_lamports(sale_ai, buyer, refund)?,
+                    Some(_) => {
+                        let buyer_quote = next_account_info(accounts_iter)?;
+                        let quote_vault = next_account_info(accounts_iter)?;
+                        let quote_mint = next_account_info(accounts_iter)?;
+                        assert_quote_accounts(&sale, quote_vault, quote_mint)?;
+                        token_transfer(
+                            token_program,
+                            quote_vault,
+                            buyer_quote,
+                            sale_ai,
+                            quote_mint,
+                            token_config,
+                            refund,
+                            &[&[SALE_SEED, sale.creator.as_ref(), &sale.id.to_le_bytes(), &[sale.bump]]],
+                        )?;
+                    }
+                }
+
+                sale.total_raised -= refund;
+                msg!("Refunded {} for {} tokens", refund, returned);
+                sale.save(sale_ai)?;
+
+                purchase.amount -= returned;
+                purchase.paid -= refund;
+                if purchase.amount == 0 {
+                    // Close the purchase so that it can't be refunded twice, its rent goes back to the buyer
+                    let lamports = purchase_ai.lamports();
+                    move_lamports(purchase_ai, buyer, lamports)?;
+                    purchase_ai.data.borrow_mut().fill(0);
+                } else {
+                    purchase.serialize(&mut *purchase_ai.data.borrow_mut())?;
+                }
+            }
+            SaleInstruction::Withdraw => {
+                msg!("Instruction: Withdraw");
+                let creator = next_account_info(accounts_iter)?;
+                let sale_ai = next_account_info(accounts_iter)?;
+                let token_program = next_account_info(accounts_iter)?;
+                let token_config = next_account_info(accounts_iter)?;
+                assert_token_program(token_program)?;
+
+                assert_with_msg(
+                    creator.is_signer,
+                    ProgramError::MissingRequiredSignature,
+                    "Creator must sign."
+                )?;
+                let mut sale = load_sale(program_id, sale_ai)?;
+                assert_with_msg(
+                    sale.creator == *creator.key && sale.token_program == *token_program.key,
+                    SaleError::SaleMismatch.into(),
+                    "Accounts do not match the sale"
+                )?;
+                assert_with_msg(
+                    Clock::get()?.unix_timestamp >= sale.end_time,
+                    SaleError::NotEnded.into(),
+                    "Sale has not ended"
+                )?;
+                assert_with_msg(
+                    sale.soft_cap_reached(),
+                    SaleError::SoftCapNotReached.into(),
+                    "Soft cap not reached, proceeds are locked"
+                )?;
+                assert_with_msg(
+                    !sale.withdrawn,
+                    SaleError::AlreadyWithdrawn.into(),
+                    "Proceeds already withdrawn"
+                )?;
+
+                match sale.quote_mint {
+                    None => move_lamports(sale_ai, creator, sale.total_raised)?,
+                    Some(_) => {
+                        let creator_quote = next_account_info(accounts_iter)?;
+                        let quote_vault = next_account_info(accounts_iter)?;
+                        let quote_mint = next_account_info(accounts_iter)?;
+                        assert_quote_accounts(&sale, quote_vault, quote_mint)?;
+                        token_transfer(
+                            token_program,
+                            quote_vault,
+                            creator_quote,
+                            sale_ai,
+                            quote_mint,
+                            token_config,
+                            sale.total_raised,
+                            &[&[SALE_SEED, sale.creator.as_ref(), &sale.id.to_le_bytes(), &[sale.bump]]],
+                        )?;
+                    }
+                }
+
+                sale.withdrawn = true;
+                msg!("Withdrew {}", sale.total_raised);
+                sale.save(sale_ai)?;
+            }
+        }
+
+        Ok(())
+    }
+}
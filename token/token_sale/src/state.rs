@@ -0,0 +1,117 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+pub const SALE_SEED: &[u8] = b"sale";
+pub const MINT_SEED: &[u8] = b"mint";
+pub const QUOTE_VAULT_SEED: &[u8] = b"quote_vault";
+pub const PURCHASE_SEED: &[u8] = b"purchase";
+
+/* A sale is a PDA seeded by [SALE_SEED, creator, id]. It is the authority of the sold mint,
+ seeded by [MINT_SEED, sale], and mints tokens to buyers as they pay.
+ Buyers pay in lamports, held by the sale account itself, or in quote_mint tokens,
+ held by the quote vault seeded by [QUOTE_VAULT_SEED, sale].
+ All caps are expressed in sold tokens.
+*/
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Sale {
+    pub creator: Pubkey,
+    pub id: u64,
+    pub token_program: Pubkey,
+    pub mint: Pubkey,
+    pub quote_mint: Option<Pubkey>, // None when the sale is paid in lamports
+    pub quote_vault: Option<Pubkey>,
+    pub price: u64, // lamports or quote tokens per sold token
+    pub start_time: i64,
+    pub end_time: i64,
+    pub soft_cap: u64, // below this buyers can get a refund once the sale has ended
+    pub hard_cap: u64,
+    pub per_buyer_cap: u64,
+    pub total_sold: u64,
+    pub total_raised: u64,
+    pub withdrawn: bool,
+    pub bump: u8,
+}
+
+impl Sale {
+    // Size with every optional field set
+    pub const LEN: usize = 32 + 8 + 32 * 2 + (1 + 32) * 2 + 8 * 8 + 1 + 1;
+
+    // The Option fields make the serialized size variable, ignore trailing bytes
+    pub fn load(ai: &AccountInfo) -> Result<Self, ProgramError> {
+        Ok(Self::deserialize(&mut &ai.data.borrow()[..])?)
+    }
+
+    pub fn save(&self, ai: &AccountInfo) -> Result<(), ProgramError> {
+        Ok(self.serialize(&mut *ai.data.borrow_mut())?)
+    }
+
+    // Whether the sale succeeded, only meaningful once it has ended
+    pub fn soft_cap_reached(&self) -> bool {
+        self.total_sold >= self.soft_cap
+    }
+}
+
+// What a buyer bought and paid, a PDA seeded by [PURCHASE_SEED, sale, buyer]
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
+pub struct Purchase {
+    pub buyer: Pubkey,
+    pub sale: Pubkey,
+    pub amount: u64,
+    pub paid: u64,
+    pub bump: u8,
+}
+
+impl Purchase {
+    pub const LEN: usize = 32 * 2 + 8 * 2 + 1;
+
+    // Share of paid refunded for giving back amount of the tokens bought, rounded down.
+    // Giving back every remaining token refunds everything left, so no dust is kept
+    pub fn refund_for(&self, amount: u64) -> Option<u64> {
+        if amount > self.amount {
+            return None
+        }
+        if amount == self.amount {
+            return Some(self.paid)
+        }
+        u64::try_from(self.paid as u128 * amount as u128 / self.amount as u128).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn purchase(amount: u64, paid: u64) -> Purchase {
+        Purchase {buyer: Pubkey::new_unique(), sale: Pubkey::new_unique(), amount, paid, bump: 255}
+    }
+
+    #[test]
+    fn refund_is_pro_rata() {
+        let purchase = purchase(100, 1_000);
+        assert_eq!(purchase.refund_for(0), Some(0));
+        assert_eq!(purchase.refund_for(1), Some(10));
+        assert_eq!(purchase.refund_for(40), Some(400));
+        assert_eq!(purchase.refund_for(100), Some(1_000));
+        assert_eq!(purchase.refund_for(101), None);
+    }
+
+    #[test]
+    fn partial_refunds_add_up_to_the_payment() {
+        let mut purchase = purchase(3, 100);
+        let mut refunded = 0;
+        for _ in 0..3 {
+            let refund = purchase.refund_for(1).unwrap();
+            refunded += refund;
+            purchase.amount -= 1;
+            purchase.paid -= refund;
+        }
+        assert_eq!(refunded, 100);
+        assert_eq!(purchase.paid, 0);
+    }
+
+    #[test]
+    fn refund_does_not_overflow() {
+        let purchase = purchase(u64::MAX, u64::MAX);
+        assert_eq!(purchase.refund_for(u64::MAX - 1), Some(u64::MAX - 1));
+    }
+}